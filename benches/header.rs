@@ -0,0 +1,42 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+use neshdr::header::encoder::Encoder;
+use neshdr::header::view::HeaderView;
+use std::hint::black_box;
+
+// MMC3 with 256K PRG-ROM, 128K CHR-ROM and 8K PRG-RAM
+const HEADER: [u8; 16] = [
+    0x4E, 0x45, 0x53, 0x1A, 0x10, 0x10, 0x40, 0x08, 0x00, 0x00, 0x07, 0x00, 0x00, 0x00, 0x00, 0x00,
+];
+
+// Read the fields which a collection scan looks at
+fn scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan");
+    group.bench_function("encoder", |b| {
+        b.iter(|| {
+            let header = Encoder::new().encode(black_box(&HEADER[..])).unwrap();
+            (header.mapper, header.submapper, header.prg_rom_size.bytes())
+        })
+    });
+    group.bench_function("view", |b| {
+        b.iter(|| {
+            let view = HeaderView::new(black_box(&HEADER)).unwrap();
+            (
+                view.mapper(),
+                view.submapper(),
+                view.prg_rom_size().unwrap().bytes(),
+            )
+        })
+    });
+    group.bench_function("view_to_header", |b| {
+        b.iter(|| {
+            HeaderView::new(black_box(&HEADER))
+                .unwrap()
+                .to_header()
+                .unwrap()
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, scan);
+criterion_main!(benches);
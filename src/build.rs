@@ -0,0 +1,40 @@
+//! Helpers for build scripts which assemble ROMs from a header definition.
+
+use crate::header::Header;
+use crate::header::diagnostic::Diagnostic;
+use crate::header::format::DefinitionFormat;
+use crate::header::nes_file::NesFile;
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::fs;
+use std::path::Path;
+
+/// Returns a whole ROM of the header read from the definition file followed by PRG-ROM and CHR-ROM.
+///
+/// The format of the definition is detected from its extension, defaulting to JSON. Sizes of the
+/// payloads must agree with the header, which therefore can't have trainer or PlayChoice-10 ROMs.
+/// Prints `cargo:rerun-if-changed` for the definition file, so call it only from a build script.
+pub fn assemble<P: AsRef<Path>>(definition: P, prg_rom: &[u8], chr_rom: &[u8]) -> Result<Vec<u8>> {
+    let path = definition.as_ref();
+    println!("cargo:rerun-if-changed={}", path.display());
+
+    let source = fs::read_to_string(path)
+        .with_context(|| format!("failed to open definition file: {}", path.display()))?;
+    let format = DefinitionFormat::from_path(path).unwrap_or(DefinitionFormat::Json);
+    let header = Header::from_definition(source.as_bytes(), format)
+        .map_err(|e| Diagnostic::locate(&path.display().to_string(), &source, &e))?;
+    let file = NesFile {
+        header,
+        original_header: None,
+        misc_roms: 0,
+        expansion_device: 0,
+        trainer: None,
+        prg_rom: Cow::Borrowed(prg_rom),
+        chr_rom: Cow::Borrowed(chr_rom),
+        inst_rom: None,
+        prom: None,
+        misc_rom: Cow::Borrowed(&[]),
+        trailing: Cow::Borrowed(&[]),
+    };
+    file.to_bytes()
+}
@@ -0,0 +1,926 @@
+//! Allocation-free codec of NES 2.0 file header, which works without std.
+//!
+//! Fields are plain integers: enum fields are the discriminants written in
+//! the header, ROM sizes are the raw 12-bit values and RAM sizes are shift
+//! counts. Typed fields are provided by `header::view` on top of this.
+
+use core::fmt::{self, Display, Formatter};
+
+/// Error of the codec
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Error {
+    /// Doesn't start with "NES\x1a"
+    InvalidMagic,
+    /// Identifier bits of byte 7 aren't NES 2.0
+    NotNes2,
+    MapperTooLarge(u16),
+    SubmapperTooLarge(u8),
+    /// Discriminant of the named field is out of range
+    InvalidDiscriminant(&'static str, u8),
+    /// Raw ROM size is more than 12 bits
+    RomSizeTooLarge(u16),
+    /// RAM shift count is more than 4 bits
+    RamShiftTooLarge(u8),
+}
+
+impl Display for Error {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidMagic => write!(f, "starts with invalid byte sequence"),
+            Error::NotNes2 => write!(f, "invalid NES 2.0 identifier"),
+            Error::MapperTooLarge(mapper) => write!(f, "mapper number is too large: {mapper}"),
+            Error::SubmapperTooLarge(submapper) => {
+                write!(f, "submapper number is too large: {submapper}")
+            }
+            Error::InvalidDiscriminant(field, value) => write!(f, "invalid {field} type: {value}"),
+            Error::RomSizeTooLarge(size) => write!(f, "too large raw rom size: {size:#x}"),
+            Error::RamShiftTooLarge(shift) => write!(f, "too large ram shift count: {shift}"),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for Error {}
+
+impl Error {
+    /// Returns message without the value, which is usable in const context
+    pub const fn message(&self) -> &'static str {
+        match self {
+            Error::InvalidMagic => "starts with invalid byte sequence",
+            Error::NotNes2 => "invalid NES 2.0 identifier",
+            Error::MapperTooLarge(_) => "mapper number is too large",
+            Error::SubmapperTooLarge(_) => "submapper number is too large",
+            Error::InvalidDiscriminant(_, _) => "invalid discriminant",
+            Error::RomSizeTooLarge(_) => "too large raw rom size",
+            Error::RamShiftTooLarge(_) => "too large ram shift count",
+        }
+    }
+}
+
+/// Checks if the bytes start with NES 2.0 identifiers
+pub const fn check(bytes: &[u8; 16]) -> Result<(), Error> {
+    if !matches!(bytes, [0x4e, 0x45, 0x53, 0x1a, ..]) {
+        Err(Error::InvalidMagic)
+    } else if bytes[7] & 0b1100 != 0b1000 {
+        Err(Error::NotNes2)
+    } else {
+        Ok(())
+    }
+}
+
+/// Format of header, told by identifier bits of byte 7
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    Ines,
+    Nes2,
+    /// iNES written before byte 7 was defined, which may have garbage in bytes 7 to 15
+    Archaic,
+}
+
+impl Format {
+    pub const fn name(&self) -> &'static str {
+        match self {
+            Format::Ines => "iNES",
+            Format::Nes2 => "NES 2.0",
+            Format::Archaic => "archaic",
+        }
+    }
+}
+
+/// Returns format of the header, or error if it doesn't start with "NES\x1a"
+pub const fn format(bytes: &[u8; 16]) -> Result<Format, Error> {
+    if !matches!(bytes, [0x4e, 0x45, 0x53, 0x1a, ..]) {
+        return Err(Error::InvalidMagic);
+    }
+    match bytes[7] & 0b1100 {
+        0b1000 => Ok(Format::Nes2),
+        0b0000 if matches!(bytes, [.., 0, 0, 0, 0]) => Ok(Format::Ines),
+        _ => Ok(Format::Archaic),
+    }
+}
+
+/// Write NES 2.0 identifiers into the bytes
+pub const fn init(bytes: &mut [u8; 16]) {
+    bytes[0] = 0x4e;
+    bytes[1] = 0x45;
+    bytes[2] = 0x53;
+    bytes[3] = 0x1a;
+    bytes[7] = (bytes[7] & !0b1100) | 0b1000;
+}
+
+/// Convert iNES header into NES 2.0 header, assuming 8K PRG-NVRAM for battery backed cartridge
+pub const fn ines_to_nes2(bytes: &mut [u8; 16]) {
+    let pal = bytes[9] & 1 != 0;
+    bytes[7] = (bytes[7] & 0xf3) | 0b1000;
+    let mut i = 8;
+    while i < 16 {
+        bytes[i] = 0;
+        i += 1;
+    }
+    if battery(bytes) {
+        bytes[10] = 7 << 4;
+    }
+    if pal {
+        bytes[12] = 1;
+    }
+}
+
+pub const fn mapper(bytes: &[u8; 16]) -> u16 {
+    let mut mapper = 0;
+    mapper |= ((bytes[6] & 0xf0) as u16) >> 4;
+    mapper |= (bytes[7] & 0xf0) as u16;
+    mapper |= ((bytes[8] & 0xf) as u16) << 8;
+    mapper
+}
+
+pub const fn set_mapper(bytes: &mut [u8; 16], mapper: u16) -> Result<(), Error> {
+    if mapper > 0xFFF {
+        return Err(Error::MapperTooLarge(mapper));
+    }
+    bytes[6] = (bytes[6] & 0x0f) | ((mapper & 0xF) << 4) as u8;
+    bytes[7] = (bytes[7] & 0x0f) | (mapper & 0xF0) as u8;
+    bytes[8] = (bytes[8] & 0xf0) | ((mapper & 0xF00) >> 8) as u8;
+    Ok(())
+}
+
+pub const fn submapper(bytes: &[u8; 16]) -> u8 {
+    (bytes[8] & 0xf0) >> 4
+}
+
+pub const fn set_submapper(bytes: &mut [u8; 16], submapper: u8) -> Result<(), Error> {
+    if submapper > 0xF {
+        return Err(Error::SubmapperTooLarge(submapper));
+    }
+    bytes[8] = (bytes[8] & 0x0f) | (submapper << 4);
+    Ok(())
+}
+
+/// Returns 0 for horizontal, 1 for vertical and 2 for four screens
+pub const fn mirroring(bytes: &[u8; 16]) -> u8 {
+    if bytes[6] & 0b1000 != 0 {
+        2
+    } else {
+        bytes[6] & 0b0001
+    }
+}
+
+pub const fn set_mirroring(bytes: &mut [u8; 16], mirroring: u8) -> Result<(), Error> {
+    bytes[6] &= !0b1001;
+    bytes[6] |= match mirroring {
+        0 => 0b0000,
+        1 => 0b0001,
+        2 => 0b1000,
+        value => return Err(Error::InvalidDiscriminant("mirroring", value)),
+    };
+    Ok(())
+}
+
+pub const fn battery(bytes: &[u8; 16]) -> bool {
+    bytes[6] & 0b10 != 0
+}
+
+pub const fn set_battery(bytes: &mut [u8; 16], battery: bool) {
+    bytes[6] = (bytes[6] & !0b10) | if battery { 0b10 } else { 0 };
+}
+
+pub const fn trainer(bytes: &[u8; 16]) -> bool {
+    bytes[6] & 0b100 != 0
+}
+
+pub const fn set_trainer(bytes: &mut [u8; 16], trainer: bool) {
+    bytes[6] = (bytes[6] & !0b100) | if trainer { 0b100 } else { 0 };
+}
+
+/// Returns console type, where 0x3 and more are extended console types of byte 13
+pub const fn console(bytes: &[u8; 16]) -> u8 {
+    match bytes[7] & 0b11 {
+        0b11 => bytes[13] & 0xf,
+        console => console,
+    }
+}
+
+/// Set console type, which shares byte 13 with Vs System types. Byte 13 is
+/// cleared when the type changes, since its meaning depends on the type.
+pub const fn set_console(bytes: &mut [u8; 16], console: u8) -> Result<(), Error> {
+    if console > 0xF {
+        return Err(Error::InvalidDiscriminant("console", console));
+    }
+    if self::console(bytes) != console {
+        bytes[13] = 0;
+    }
+    bytes[7] &= !0b11;
+    if console <= 0x2 {
+        bytes[7] |= console;
+    } else {
+        bytes[7] |= 0b11;
+        bytes[13] = console;
+    }
+    Ok(())
+}
+
+/// Returns 0 for RP2C02, 1 for RP2C07, 2 for multiple and 3 for UA6538
+pub const fn timing(bytes: &[u8; 16]) -> u8 {
+    bytes[12] & 0b11
+}
+
+pub const fn set_timing(bytes: &mut [u8; 16], timing: u8) -> Result<(), Error> {
+    if timing > 0b11 {
+        return Err(Error::InvalidDiscriminant("timing", timing));
+    }
+    bytes[12] = (bytes[12] & !0b11) | timing;
+    Ok(())
+}
+
+/// Returns PPU type, which is meaningful only for Vs System
+pub const fn vs_system_ppu(bytes: &[u8; 16]) -> u8 {
+    bytes[13] & 0xf
+}
+
+pub const fn set_vs_system_ppu(bytes: &mut [u8; 16], ppu: u8) -> Result<(), Error> {
+    if ppu > 0xF {
+        return Err(Error::InvalidDiscriminant("vs system ppu", ppu));
+    }
+    bytes[13] = (bytes[13] & 0xf0) | ppu;
+    Ok(())
+}
+
+/// Returns hardware type, which is meaningful only for Vs System
+pub const fn vs_system_hardware(bytes: &[u8; 16]) -> u8 {
+    (bytes[13] & 0xf0) >> 4
+}
+
+pub const fn set_vs_system_hardware(bytes: &mut [u8; 16], hardware: u8) -> Result<(), Error> {
+    if hardware > 0xF {
+        return Err(Error::InvalidDiscriminant("vs system hardware", hardware));
+    }
+    bytes[13] = (bytes[13] & 0x0f) | (hardware << 4);
+    Ok(())
+}
+
+/// Returns raw 12-bit PRG-ROM size, see `rom_size_bytes`
+pub const fn prg_rom_size(bytes: &[u8; 16]) -> u16 {
+    bytes[4] as u16 | ((bytes[9] & 0xf) as u16) << 8
+}
+
+pub const fn set_prg_rom_size(bytes: &mut [u8; 16], size: u16) -> Result<(), Error> {
+    if size > 0xFFF {
+        return Err(Error::RomSizeTooLarge(size));
+    }
+    bytes[4] = size as u8;
+    bytes[9] = (bytes[9] & 0xf0) | (size >> 8) as u8;
+    Ok(())
+}
+
+/// Returns raw 12-bit CHR-ROM size, see `rom_size_bytes`
+pub const fn chr_rom_size(bytes: &[u8; 16]) -> u16 {
+    bytes[5] as u16 | ((bytes[9] & 0xf0) as u16) << 4
+}
+
+pub const fn set_chr_rom_size(bytes: &mut [u8; 16], size: u16) -> Result<(), Error> {
+    if size > 0xFFF {
+        return Err(Error::RomSizeTooLarge(size));
+    }
+    bytes[5] = size as u8;
+    bytes[9] = (bytes[9] & 0x0f) | ((size >> 4) & 0xf0) as u8;
+    Ok(())
+}
+
+/// Returns shift count of PRG-RAM size, see `ram_size_bytes`
+pub const fn prg_ram_shift(bytes: &[u8; 16]) -> u8 {
+    bytes[10] & 0xf
+}
+
+pub const fn set_prg_ram_shift(bytes: &mut [u8; 16], shift: u8) -> Result<(), Error> {
+    set_nibble(&mut bytes[10], 0, shift)
+}
+
+/// Returns shift count of PRG-NVRAM size, see `ram_size_bytes`
+pub const fn prg_nvram_shift(bytes: &[u8; 16]) -> u8 {
+    (bytes[10] & 0xf0) >> 4
+}
+
+pub const fn set_prg_nvram_shift(bytes: &mut [u8; 16], shift: u8) -> Result<(), Error> {
+    set_nibble(&mut bytes[10], 4, shift)
+}
+
+/// Returns shift count of CHR-RAM size, see `ram_size_bytes`
+pub const fn chr_ram_shift(bytes: &[u8; 16]) -> u8 {
+    bytes[11] & 0xf
+}
+
+pub const fn set_chr_ram_shift(bytes: &mut [u8; 16], shift: u8) -> Result<(), Error> {
+    set_nibble(&mut bytes[11], 0, shift)
+}
+
+/// Returns shift count of CHR-NVRAM size, see `ram_size_bytes`
+pub const fn chr_nvram_shift(bytes: &[u8; 16]) -> u8 {
+    (bytes[11] & 0xf0) >> 4
+}
+
+pub const fn set_chr_nvram_shift(bytes: &mut [u8; 16], shift: u8) -> Result<(), Error> {
+    set_nibble(&mut bytes[11], 4, shift)
+}
+
+const fn set_nibble(byte: &mut u8, offset: u8, shift: u8) -> Result<(), Error> {
+    if shift > 0xF {
+        return Err(Error::RamShiftTooLarge(shift));
+    }
+    *byte = (*byte & !(0xf << offset)) | (shift << offset);
+    Ok(())
+}
+
+/// Returns bytes of raw ROM size in units of the given bytes, or `None` on overflow
+pub const fn rom_size_bytes(size: u16, unit: u64) -> Option<u64> {
+    if size >> 8 == 0xF {
+        let exponent = (size & 0b1111_1100) >> 2;
+        let multiplier = (size & 0b0000_0011) as u64 * 2 + 1;
+        match 1u64.checked_shl(exponent as u32) {
+            Some(base) => base.checked_mul(multiplier),
+            None => None,
+        }
+    } else {
+        (size as u64).checked_mul(unit)
+    }
+}
+
+/// Returns bytes of RAM size of the shift count
+pub const fn ram_size_bytes(shift: u8) -> u64 {
+    if shift == 0 { 0 } else { 64 << shift }
+}
+
+/// Parse size literal like `128K`, `1MiB` or `0x2000` into bytes, or `None` if invalid
+pub const fn parse_size(literal: &str) -> Option<u64> {
+    let bytes = literal.as_bytes();
+    let (radix, mut i) = match bytes {
+        [b'0', b'x' | b'X', ..] => (16, 2),
+        _ => (10, 0),
+    };
+    let start = i;
+    let mut number: u64 = 0;
+    while i < bytes.len() {
+        let digit = match bytes[i] {
+            c @ b'0'..=b'9' => c - b'0',
+            c @ b'a'..=b'f' if radix == 16 => c - b'a' + 10,
+            c @ b'A'..=b'F' if radix == 16 => c - b'A' + 10,
+            _ => break,
+        };
+        number = match number.checked_mul(radix) {
+            Some(number) => match number.checked_add(digit as u64) {
+                Some(number) => number,
+                None => return None,
+            },
+            None => return None,
+        };
+        i += 1;
+    }
+    if i == start {
+        return None;
+    }
+    let unit = match bytes.split_at(i).1 {
+        [] => 1,
+        [b'k' | b'K'] | [b'k' | b'K', b'b' | b'B'] | [b'k' | b'K', b'i', b'b' | b'B'] => 1024,
+        [b'm' | b'M'] | [b'm' | b'M', b'b' | b'B'] | [b'm' | b'M', b'i', b'b' | b'B'] => {
+            1024 * 1024
+        }
+        _ => return None,
+    };
+    number.checked_mul(unit)
+}
+
+/// Returns raw ROM size of the bytes in units of the given bytes, preferring
+/// unit count over exponent-multiplier, or `None` if neither can express it
+pub const fn rom_size_of(bytes: u64, unit: u64) -> Option<u16> {
+    if bytes.is_multiple_of(unit) && bytes / unit <= 0xEFF {
+        return Some((bytes / unit) as u16);
+    }
+    let mut exponent = 0;
+    while exponent <= 60 {
+        let mut multiplier = 0;
+        while multiplier <= 3 {
+            if bytes == (1 << exponent) * (multiplier * 2 + 1) {
+                return Some(0xF00 | (exponent << 2) as u16 | multiplier as u16);
+            }
+            multiplier += 1;
+        }
+        exponent += 1;
+    }
+    None
+}
+
+/// Returns shift count of RAM size of the bytes, or `None` if it isn't 64 << shift
+pub const fn ram_shift_of(bytes: u64) -> Option<u8> {
+    if bytes == 0 {
+        return Some(0);
+    }
+    let mut shift = 1;
+    while shift <= 0xF {
+        if 64 << shift == bytes {
+            return Some(shift);
+        }
+        shift += 1;
+    }
+    None
+}
+
+/// Discriminants of the enum fields, named after the variants of the typed enums
+#[allow(non_upper_case_globals)]
+pub mod variants {
+    pub mod mirroring {
+        pub const Horizontal: u8 = 0;
+        pub const Vertical: u8 = 1;
+        pub const FourScreens: u8 = 2;
+    }
+
+    pub mod console {
+        pub const Nes: u8 = 0x00;
+        pub const VsSystem: u8 = 0x01;
+        pub const Playchoice10: u8 = 0x02;
+        pub const NesWithDecimal: u8 = 0x03;
+        pub const NesWithEPSM: u8 = 0x04;
+        pub const VT01: u8 = 0x05;
+        pub const VT02: u8 = 0x06;
+        pub const VT03: u8 = 0x07;
+        pub const VT09: u8 = 0x08;
+        pub const VT32: u8 = 0x09;
+        pub const VT369: u8 = 0x0a;
+        pub const UM6539: u8 = 0x0b;
+        pub const FamicomNetworkSystem: u8 = 0x0c;
+        pub const Reserved0D: u8 = 0x0d;
+        pub const Reserved0E: u8 = 0x0e;
+        pub const Reserved0F: u8 = 0x0f;
+    }
+
+    pub mod timing {
+        pub const RP2C02: u8 = 0;
+        pub const RP2C07: u8 = 1;
+        pub const Multiple: u8 = 2;
+        pub const UA6538: u8 = 3;
+    }
+
+    pub mod vs_system_ppu {
+        pub const Any: u8 = 0x00;
+        pub const Reserved01: u8 = 0x01;
+        pub const RP2C04_0001: u8 = 0x02;
+        pub const RP2C04_0002: u8 = 0x03;
+        pub const RP2C04_0003: u8 = 0x04;
+        pub const RP2C04_0004: u8 = 0x05;
+        pub const Reserved06: u8 = 0x06;
+        pub const Reserved07: u8 = 0x07;
+        pub const RC2C05_01: u8 = 0x08;
+        pub const RC2C05_02: u8 = 0x09;
+        pub const RC2C05_03: u8 = 0x0a;
+        pub const RC2C05_04: u8 = 0x0b;
+        pub const Reserved0C: u8 = 0x0c;
+        pub const Reserved0D: u8 = 0x0d;
+        pub const Reserved0E: u8 = 0x0e;
+        pub const Reserved0F: u8 = 0x0f;
+    }
+
+    pub mod vs_system_hardware {
+        pub const UnisystemNormal: u8 = 0;
+        pub const UnisystemRBIBaseball: u8 = 1;
+        pub const UnisystemTKOBoxing: u8 = 2;
+        pub const UnisystemSuperXevious: u8 = 3;
+        pub const UnisystemIceClimberJapan: u8 = 4;
+        pub const DualSystemNormal: u8 = 5;
+        pub const DualSystemRaidOnBungelingBay: u8 = 6;
+    }
+}
+
+/// Build 16 bytes of NES 2.0 header at compile time, where a field out of
+/// range or a size which the header can't express is a compile error.
+///
+/// Enum fields take the variant names of the typed enums, sizes take literals
+/// like `128K`, `1MiB` or `0x2000`, and omitted fields are zero.
+///
+/// ```
+/// const HEADER: [u8; 16] = neshdr::nes_header! {
+///     mapper: 4,
+///     prg_rom: 128K,
+///     chr_rom: 128K,
+///     mirroring: Vertical,
+///     battery: true,
+/// };
+/// assert_eq!(HEADER, *b"NES\x1a\x08\x10\x43\x08\0\0\0\0\0\0\0\0");
+/// ```
+///
+/// 100K PRG-ROM is neither a multiple of 16K nor an exponent-multiplier size.
+///
+/// ```compile_fail
+/// const HEADER: [u8; 16] = neshdr::nes_header! {
+///     mapper: 0,
+///     prg_rom: 100K,
+/// };
+/// ```
+#[macro_export]
+macro_rules! nes_header {
+    { $($field:ident : $value:tt),* $(,)? } => {{
+        const BYTES: [u8; 16] = {
+            #[allow(unused_mut)]
+            let mut raw = $crate::codec::RawHeader::new();
+            $($crate::nes_header!(@field raw, $field, $value);)*
+            match raw.to_bytes() {
+                Ok(bytes) => bytes,
+                Err(e) => panic!("{}", e.message()),
+            }
+        };
+        BYTES
+    }};
+    (@field $raw:ident, mapper, $value:literal) => { $raw.mapper = $value; };
+    (@field $raw:ident, submapper, $value:literal) => { $raw.submapper = $value; };
+    (@field $raw:ident, battery, $value:literal) => { $raw.battery = $value; };
+    (@field $raw:ident, trainer, $value:literal) => { $raw.trainer = $value; };
+    (@field $raw:ident, mirroring, $value:ident) => {
+        $raw.mirroring = $crate::codec::variants::mirroring::$value;
+    };
+    (@field $raw:ident, console, $value:ident) => {
+        $raw.console = $crate::codec::variants::console::$value;
+    };
+    (@field $raw:ident, timing, $value:ident) => {
+        $raw.timing = $crate::codec::variants::timing::$value;
+    };
+    (@field $raw:ident, vs_system_ppu, $value:ident) => {
+        $raw.vs_system_ppu = $crate::codec::variants::vs_system_ppu::$value;
+    };
+    (@field $raw:ident, vs_system_hardware, $value:ident) => {
+        $raw.vs_system_hardware = $crate::codec::variants::vs_system_hardware::$value;
+    };
+    (@field $raw:ident, prg_rom, $value:tt) => {
+        $raw.prg_rom_size = $crate::nes_header!(@rom $value, 16 * 1024, "invalid PRG-ROM size");
+    };
+    (@field $raw:ident, chr_rom, $value:tt) => {
+        $raw.chr_rom_size = $crate::nes_header!(@rom $value, 8 * 1024, "invalid CHR-ROM size");
+    };
+    (@field $raw:ident, prg_ram, $value:tt) => {
+        $raw.prg_ram_shift = $crate::nes_header!(@ram $value, "invalid PRG-RAM size");
+    };
+    (@field $raw:ident, prg_nvram, $value:tt) => {
+        $raw.prg_nvram_shift = $crate::nes_header!(@ram $value, "invalid PRG-NVRAM size");
+    };
+    (@field $raw:ident, chr_ram, $value:tt) => {
+        $raw.chr_ram_shift = $crate::nes_header!(@ram $value, "invalid CHR-RAM size");
+    };
+    (@field $raw:ident, chr_nvram, $value:tt) => {
+        $raw.chr_nvram_shift = $crate::nes_header!(@ram $value, "invalid CHR-NVRAM size");
+    };
+    (@rom $value:tt, $unit:expr, $message:literal) => {
+        match $crate::codec::parse_size(stringify!($value)) {
+            Some(bytes) => match $crate::codec::rom_size_of(bytes, $unit) {
+                Some(size) => size,
+                None => panic!($message),
+            },
+            None => panic!($message),
+        }
+    };
+    (@ram $value:tt, $message:literal) => {
+        match $crate::codec::parse_size(stringify!($value)) {
+            Some(bytes) => match $crate::codec::ram_shift_of(bytes) {
+                Some(shift) => shift,
+                None => panic!($message),
+            },
+            None => panic!($message),
+        }
+    };
+}
+
+// `?` which works in const fn
+macro_rules! try_const {
+    ($result:expr) => {
+        match $result {
+            Ok(value) => value,
+            Err(e) => return Err(e),
+        }
+    };
+}
+
+/// Every field of NES 2.0 file header in integers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RawHeader {
+    pub mapper: u16,
+    pub submapper: u8,
+    pub mirroring: u8,
+    pub battery: bool,
+    pub trainer: bool,
+    pub console: u8,
+    pub timing: u8,
+    pub vs_system_ppu: u8,
+    pub vs_system_hardware: u8,
+    pub prg_rom_size: u16,
+    pub chr_rom_size: u16,
+    pub prg_ram_shift: u8,
+    pub prg_nvram_shift: u8,
+    pub chr_ram_shift: u8,
+    pub chr_nvram_shift: u8,
+    /// Byte 14, count of misc ROMs
+    pub misc_roms: u8,
+    /// Byte 15, default expansion device
+    pub expansion_device: u8,
+}
+
+impl RawHeader {
+    /// Header whose fields are all zero
+    pub const fn new() -> Self {
+        RawHeader {
+            mapper: 0,
+            submapper: 0,
+            mirroring: 0,
+            battery: false,
+            trainer: false,
+            console: 0,
+            timing: 0,
+            vs_system_ppu: 0,
+            vs_system_hardware: 0,
+            prg_rom_size: 0,
+            chr_rom_size: 0,
+            prg_ram_shift: 0,
+            prg_nvram_shift: 0,
+            chr_ram_shift: 0,
+            chr_nvram_shift: 0,
+            misc_roms: 0,
+            expansion_device: 0,
+        }
+    }
+
+    pub const fn from_bytes(bytes: &[u8; 16]) -> Result<Self, Error> {
+        try_const!(check(bytes));
+        // Byte 13 holds either extended console type or Vs System types
+        let vs_system = console(bytes) == 0x1;
+        Ok(RawHeader {
+            mapper: mapper(bytes),
+            submapper: submapper(bytes),
+            mirroring: mirroring(bytes),
+            battery: battery(bytes),
+            trainer: trainer(bytes),
+            console: console(bytes),
+            timing: timing(bytes),
+            vs_system_ppu: if vs_system { vs_system_ppu(bytes) } else { 0 },
+            vs_system_hardware: if vs_system {
+                vs_system_hardware(bytes)
+            } else {
+                0
+            },
+            prg_rom_size: prg_rom_size(bytes),
+            chr_rom_size: chr_rom_size(bytes),
+            prg_ram_shift: prg_ram_shift(bytes),
+            prg_nvram_shift: prg_nvram_shift(bytes),
+            chr_ram_shift: chr_ram_shift(bytes),
+            chr_nvram_shift: chr_nvram_shift(bytes),
+            misc_roms: bytes[14],
+            expansion_device: bytes[15],
+        })
+    }
+
+    pub const fn to_bytes(&self) -> Result<[u8; 16], Error> {
+        let mut bytes = [0; 16];
+        init(&mut bytes);
+        try_const!(set_mapper(&mut bytes, self.mapper));
+        try_const!(set_submapper(&mut bytes, self.submapper));
+        try_const!(set_mirroring(&mut bytes, self.mirroring));
+        set_battery(&mut bytes, self.battery);
+        set_trainer(&mut bytes, self.trainer);
+        try_const!(set_console(&mut bytes, self.console));
+        try_const!(set_timing(&mut bytes, self.timing));
+        if self.console == 0x1 {
+            try_const!(set_vs_system_ppu(&mut bytes, self.vs_system_ppu));
+            try_const!(set_vs_system_hardware(&mut bytes, self.vs_system_hardware));
+        }
+        try_const!(set_prg_rom_size(&mut bytes, self.prg_rom_size));
+        try_const!(set_chr_rom_size(&mut bytes, self.chr_rom_size));
+        try_const!(set_prg_ram_shift(&mut bytes, self.prg_ram_shift));
+        try_const!(set_prg_nvram_shift(&mut bytes, self.prg_nvram_shift));
+        try_const!(set_chr_ram_shift(&mut bytes, self.chr_ram_shift));
+        try_const!(set_chr_nvram_shift(&mut bytes, self.chr_nvram_shift));
+        bytes[14] = self.misc_roms;
+        bytes[15] = self.expansion_device;
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Every field is non-zero so that a lost bit shows up
+    const FULL: RawHeader = RawHeader {
+        mapper: 0xABC,
+        submapper: 0xD,
+        mirroring: variants::mirroring::FourScreens,
+        battery: true,
+        trainer: true,
+        console: variants::console::FamicomNetworkSystem,
+        timing: variants::timing::UA6538,
+        vs_system_ppu: 0,
+        vs_system_hardware: 0,
+        prg_rom_size: 0xF37,
+        chr_rom_size: 0xEFF,
+        prg_ram_shift: 0x1,
+        prg_nvram_shift: 0x7,
+        chr_ram_shift: 0x9,
+        chr_nvram_shift: 0xF,
+        misc_roms: 0x3,
+        expansion_device: 0x2A,
+    };
+
+    #[test]
+    fn round_trip_every_field() {
+        let vs_system = RawHeader {
+            mirroring: variants::mirroring::Vertical,
+            console: variants::console::VsSystem,
+            vs_system_ppu: variants::vs_system_ppu::RC2C05_04,
+            vs_system_hardware: variants::vs_system_hardware::DualSystemRaidOnBungelingBay,
+            ..FULL
+        };
+        let zero = RawHeader::new();
+        for header in [FULL, vs_system, zero] {
+            let bytes = header.to_bytes().unwrap();
+            assert_eq!(format(&bytes), Ok(Format::Nes2));
+            assert_eq!(RawHeader::from_bytes(&bytes), Ok(header));
+        }
+    }
+
+    // Setter of bytes and the same change of the fields
+    type Setter = (fn(&mut [u8; 16]), fn(&mut RawHeader));
+
+    #[test]
+    fn setters_change_only_their_field() {
+        let bytes = FULL.to_bytes().unwrap();
+        let setters: [Setter; 13] = [
+            (|b| set_mapper(b, 0x123).unwrap(), |h| h.mapper = 0x123),
+            (|b| set_submapper(b, 0x4).unwrap(), |h| h.submapper = 0x4),
+            (|b| set_mirroring(b, 1).unwrap(), |h| h.mirroring = 1),
+            (|b| set_battery(b, false), |h| h.battery = false),
+            (|b| set_trainer(b, false), |h| h.trainer = false),
+            (|b| set_console(b, 0x5).unwrap(), |h| h.console = 0x5),
+            (|b| set_timing(b, 0x1).unwrap(), |h| h.timing = 0x1),
+            (
+                |b| set_prg_rom_size(b, 0x456).unwrap(),
+                |h| h.prg_rom_size = 0x456,
+            ),
+            (
+                |b| set_chr_rom_size(b, 0xF01).unwrap(),
+                |h| h.chr_rom_size = 0xF01,
+            ),
+            (
+                |b| set_prg_ram_shift(b, 0xE).unwrap(),
+                |h| h.prg_ram_shift = 0xE,
+            ),
+            (
+                |b| set_prg_nvram_shift(b, 0x0).unwrap(),
+                |h| h.prg_nvram_shift = 0x0,
+            ),
+            (
+                |b| set_chr_ram_shift(b, 0x2).unwrap(),
+                |h| h.chr_ram_shift = 0x2,
+            ),
+            (
+                |b| set_chr_nvram_shift(b, 0x3).unwrap(),
+                |h| h.chr_nvram_shift = 0x3,
+            ),
+        ];
+        for (set_bytes, set_field) in setters {
+            let mut bytes = bytes;
+            set_bytes(&mut bytes);
+            let mut expected = FULL;
+            set_field(&mut expected);
+            assert_eq!(RawHeader::from_bytes(&bytes), Ok(expected));
+        }
+    }
+
+    #[test]
+    fn console_change_clears_byte_13() {
+        let mut bytes = FULL.to_bytes().unwrap();
+        set_console(&mut bytes, variants::console::VsSystem).unwrap();
+        assert_eq!(vs_system_ppu(&bytes), variants::vs_system_ppu::Any);
+        assert_eq!(vs_system_hardware(&bytes), 0);
+
+        set_vs_system_ppu(&mut bytes, variants::vs_system_ppu::RP2C04_0003).unwrap();
+        set_console(&mut bytes, variants::console::VsSystem).unwrap();
+        assert_eq!(vs_system_ppu(&bytes), variants::vs_system_ppu::RP2C04_0003);
+
+        set_console(&mut bytes, variants::console::Nes).unwrap();
+        set_console(&mut bytes, variants::console::VsSystem).unwrap();
+        assert_eq!(vs_system_ppu(&bytes), variants::vs_system_ppu::Any);
+    }
+
+    #[test]
+    fn setters_reject_out_of_range() {
+        let mut bytes = FULL.to_bytes().unwrap();
+        let original = bytes;
+        assert_eq!(
+            set_mapper(&mut bytes, 0x1000),
+            Err(Error::MapperTooLarge(0x1000))
+        );
+        assert_eq!(
+            set_submapper(&mut bytes, 0x10),
+            Err(Error::SubmapperTooLarge(0x10))
+        );
+        assert_eq!(
+            set_timing(&mut bytes, 4),
+            Err(Error::InvalidDiscriminant("timing", 4))
+        );
+        assert_eq!(
+            set_prg_rom_size(&mut bytes, 0x1000),
+            Err(Error::RomSizeTooLarge(0x1000))
+        );
+        assert_eq!(
+            set_chr_nvram_shift(&mut bytes, 0x10),
+            Err(Error::RamShiftTooLarge(0x10))
+        );
+        assert_eq!(bytes, original);
+        assert_eq!(
+            RawHeader {
+                console: 0x10,
+                ..FULL
+            }
+            .to_bytes(),
+            Err(Error::InvalidDiscriminant("console", 0x10))
+        );
+    }
+
+    #[test]
+    fn check_identifiers() {
+        let mut bytes = FULL.to_bytes().unwrap();
+        bytes[7] &= !0b1100;
+        assert_eq!(check(&bytes), Err(Error::NotNes2));
+        bytes[3] = 0;
+        assert_eq!(check(&bytes), Err(Error::InvalidMagic));
+        assert_eq!(format(&bytes), Err(Error::InvalidMagic));
+    }
+
+    #[test]
+    fn parse_size_literals() {
+        assert_eq!(parse_size("0"), Some(0));
+        assert_eq!(parse_size("512"), Some(512));
+        assert_eq!(parse_size("0x2000"), Some(0x2000));
+        assert_eq!(parse_size("0XfF"), Some(0xFF));
+        assert_eq!(parse_size("128K"), Some(128 * 1024));
+        assert_eq!(parse_size("8kb"), Some(8 * 1024));
+        assert_eq!(parse_size("8KiB"), Some(8 * 1024));
+        assert_eq!(parse_size("1M"), Some(1024 * 1024));
+        assert_eq!(parse_size("2MiB"), Some(2 * 1024 * 1024));
+        assert_eq!(parse_size("0x10K"), Some(16 * 1024));
+        assert_eq!(parse_size("18446744073709551615"), Some(u64::MAX));
+    }
+
+    #[test]
+    fn parse_size_rejects_invalid() {
+        assert_eq!(parse_size(""), None);
+        assert_eq!(parse_size("K"), None);
+        assert_eq!(parse_size("0x"), None);
+        assert_eq!(parse_size("12G"), None);
+        assert_eq!(parse_size("8KIB"), None);
+        assert_eq!(parse_size("8 K"), None);
+        assert_eq!(parse_size("-1"), None);
+        assert_eq!(parse_size("ff"), None);
+        assert_eq!(parse_size("18446744073709551616"), None);
+        assert_eq!(parse_size("18014398509481984K"), None);
+    }
+
+    #[test]
+    fn rom_size_of_units_and_exponent_multiplier() {
+        const UNIT: u64 = 16 * 1024;
+        assert_eq!(rom_size_of(0, UNIT), Some(0));
+        assert_eq!(rom_size_of(UNIT, UNIT), Some(1));
+        assert_eq!(rom_size_of(0xEFF * UNIT, UNIT), Some(0xEFF));
+        // Unit count 0xF00 would be read as exponent-multiplier
+        assert_eq!(rom_size_of(0xF00 * UNIT, UNIT), None);
+        assert_eq!(rom_size_of(0x1000 * UNIT, UNIT), Some(0xF00 | 26 << 2));
+        assert_eq!(rom_size_of(8 * 1024, UNIT), Some(0xF00 | 13 << 2));
+        assert_eq!(rom_size_of(3, UNIT), Some(0xF01));
+        assert_eq!(rom_size_of(7 << 60, UNIT), Some(0xF00 | 60 << 2 | 3));
+        assert_eq!(rom_size_of(100 * 1024, UNIT), None);
+        assert_eq!(rom_size_of(u64::MAX, UNIT), None);
+    }
+
+    #[test]
+    fn rom_size_round_trip() {
+        const UNIT: u64 = 8 * 1024;
+        for raw in 0..=0xFFF {
+            let Some(bytes) = rom_size_bytes(raw, UNIT) else {
+                continue;
+            };
+            let encoded = rom_size_of(bytes, UNIT);
+            if raw < 0xF00 {
+                assert_eq!(encoded, Some(raw));
+            }
+            if let Some(encoded) = encoded {
+                assert_eq!(rom_size_bytes(encoded, UNIT), Some(bytes));
+            }
+        }
+        assert_eq!(rom_size_bytes(0xF00 | 63 << 2 | 3, UNIT), None);
+    }
+
+    #[test]
+    fn ram_shift_of_sizes() {
+        assert_eq!(ram_shift_of(0), Some(0));
+        assert_eq!(ram_shift_of(64), None);
+        assert_eq!(ram_shift_of(128), Some(1));
+        assert_eq!(ram_shift_of(8 * 1024), Some(7));
+        assert_eq!(ram_shift_of(2 * 1024 * 1024), Some(0xF));
+        assert_eq!(ram_shift_of(4 * 1024 * 1024), None);
+        assert_eq!(ram_shift_of(3000), None);
+        for shift in 0..=0xF {
+            assert_eq!(ram_shift_of(ram_size_bytes(shift)), Some(shift));
+        }
+    }
+}
@@ -1,22 +1,58 @@
+pub mod asm;
+pub mod board;
+pub mod builder;
+pub mod code;
 pub mod console;
 pub mod decoder;
+pub mod definition;
+pub mod diagnostic;
+pub mod edit;
 pub mod encoder;
+pub mod format;
+pub mod import;
+pub mod layout;
+pub mod mapper;
 pub mod mirroring;
+pub mod name;
+pub mod nes_file;
+pub mod remap;
+pub mod scan;
+pub mod schema;
+pub mod size;
 pub mod timing;
+pub mod view;
 pub mod vs_system;
 
+use crate::codec::{self, Format};
 use crate::header::decoder::Decoder;
 
+use self::asm::AsmFormat;
+use self::board::{BOARDS, Board};
+use self::builder::HeaderBuilder;
+use self::code::CodeFormat;
 use self::console::Console;
+use self::definition::Definition;
 use self::encoder::Encoder;
+use self::format::DefinitionFormat;
+use self::layout::Layout;
+use self::mapper::{mapper_name, submapper_name};
 use self::mirroring::Mirroring;
+use self::remap::Remapping;
+use self::size::{ChrRomSize, PrgRomSize, RamSize, RomSizeEncoding};
 use self::timing::Timing;
+use self::view::{HeaderView, HeaderViewMut};
 use self::vs_system::{hardware::VsSystemHardware, ppu::VsSystemPPU};
-use anyhow::Result;
+use anyhow::{Result, anyhow, bail};
 use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
 use std::io::{Read, Write};
 
-#[derive(Serialize, Deserialize, Debug, Clone)]
+/// NES 2.0 header.
+///
+/// Equality and hash compare the fields as written, so headers which encode into the same bytes
+/// may differ, like `None` and explicit default fields; `same_encoding` compares the bytes.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[serde(try_from = "Definition")]
 pub struct Header {
     pub mapper: u64,
     pub submapper: Option<u64>,
@@ -27,28 +63,349 @@ pub struct Header {
     pub timing: Option<Timing>,
     pub vs_system_ppu: Option<VsSystemPPU>,
     pub vs_system_hardware: Option<VsSystemHardware>,
-    pub prg_rom_size: String,
-    pub prg_ram_size: Option<String>,
-    pub prg_nvram_size: Option<String>,
-    pub chr_rom_size: String,
-    pub chr_ram_size: Option<String>,
-    pub chr_nvram_size: Option<String>,
+    pub prg_rom_size: PrgRomSize,
+    pub prg_ram_size: Option<RamSize>,
+    pub prg_nvram_size: Option<RamSize>,
+    pub chr_rom_size: ChrRomSize,
+    pub chr_ram_size: Option<RamSize>,
+    pub chr_nvram_size: Option<RamSize>,
 }
 
 impl Header {
+    /// Returns builder which creates a header with typed setters
+    pub fn builder() -> HeaderBuilder {
+        HeaderBuilder::new()
+    }
+
+    pub fn from_definition<R: Read>(r: R, format: DefinitionFormat) -> Result<Header> {
+        // Resolved outside of serde to keep the field of the error for diagnostics
+        let definition: Definition = format.read(r)?;
+        Header::try_from(definition)
+    }
+
+    pub fn into_definition<W: Write>(
+        self,
+        w: W,
+        format: DefinitionFormat,
+        notation: MapperNotation,
+    ) -> Result<()> {
+        let value = serde_json::to_value(&self)?;
+        let value = match (notation, value) {
+            (MapperNotation::Number, value) => value,
+            (notation, Value::Object(fields)) => Value::Object(self.name_mapper(fields, notation)),
+            (_, value) => value,
+        };
+        format.write(w, &value)
+    }
+
+    /// Read JSON definition, which is `from_definition` in JSON
     pub fn from_json<R: Read>(r: R) -> Result<Header> {
-        Ok(serde_json::from_reader(r)?)
+        Header::from_definition(r, DefinitionFormat::Json)
     }
 
-    pub fn into_json<W: Write>(self, w: W) -> Result<()> {
-        Ok(serde_json::to_writer_pretty(w, &self)?)
+    /// Write JSON definition, which is `into_definition` in JSON
+    pub fn into_json<W: Write>(self, w: W, notation: MapperNotation) -> Result<()> {
+        self.into_definition(w, DefinitionFormat::Json, notation)
     }
 
     pub fn from_bytes<R: Read>(r: R) -> Result<Header> {
         Encoder::new().encode(r)
     }
 
+    /// Read iNES header, leaving the fields which only NES 2.0 has as `None`.
+    ///
+    /// Archaic iNES may have garbage in bytes 7 to 15, so only the lower 4 bits of mapper are read.
+    pub fn from_ines(bytes: &[u8; 16]) -> Result<Header> {
+        let archaic = match codec::format(bytes)? {
+            Format::Ines => false,
+            Format::Archaic => true,
+            Format::Nes2 => bail!("not iNES but NES 2.0 header"),
+        };
+        let mut mapper = (bytes[6] >> 4) as u64;
+        let mut console = None;
+        if !archaic {
+            mapper |= (bytes[7] & 0xf0) as u64;
+            // Value 3 of the console bits is defined only in NES 2.0
+            console = match bytes[7] & 0b11 {
+                0 => Some(Console::Nes),
+                1 => Some(Console::VsSystem),
+                2 => Some(Console::Playchoice10),
+                _ => None,
+            };
+        }
+        Ok(Header {
+            mapper,
+            mirroring: Some(Mirroring::try_from(codec::mirroring(bytes))?),
+            battery: Some(codec::battery(bytes)),
+            trainer: Some(codec::trainer(bytes)),
+            console,
+            prg_rom_size: PrgRomSize::from_units(bytes[4] as u64)?,
+            chr_rom_size: ChrRomSize::from_units(bytes[5] as u64)?,
+            ..Header::default()
+        })
+    }
+
     pub fn into_bytes<W: Write>(self, w: W) -> Result<()> {
         Decoder::new(self).decode(w)
     }
+
+    /// Encode the header over the original bytes, rewriting only the fields which differ from
+    /// the original so that bits which `Header` can't hold are kept as is
+    pub fn patch(&self, original: &[u8; 16]) -> Result<[u8; 16]> {
+        // Compared in decoded form, in which `None` is the value it's written as
+        let new = HeaderView::new(&self.encode()?)?.to_header()?;
+        let old = HeaderView::new(original)?.to_header()?;
+        let mut bytes = *original;
+        let mut view = HeaderViewMut::new(&mut bytes)?;
+        if new.mapper != old.mapper {
+            view.set_mapper(new.mapper)?;
+        }
+        if new.submapper != old.submapper {
+            view.set_submapper(new.submapper.unwrap_or(0))?;
+        }
+        if let Some(mirroring) = new.mirroring.filter(|_| new.mirroring != old.mirroring) {
+            view.set_mirroring(mirroring);
+        }
+        if let Some(battery) = new.battery.filter(|_| new.battery != old.battery) {
+            view.set_battery(battery);
+        }
+        if let Some(trainer) = new.trainer.filter(|_| new.trainer != old.trainer) {
+            view.set_trainer(trainer);
+        }
+        // Console is set before Vs System types, which it clears when changed
+        if let Some(console) = new.console.filter(|_| new.console != old.console) {
+            view.set_console(console);
+        }
+        if let Some(timing) = new.timing.filter(|_| new.timing != old.timing) {
+            view.set_timing(timing);
+        }
+        if let Some(ppu) = new
+            .vs_system_ppu
+            .filter(|_| new.vs_system_ppu != old.vs_system_ppu)
+        {
+            view.set_vs_system_ppu(ppu);
+        }
+        if let Some(hardware) = new
+            .vs_system_hardware
+            .filter(|_| new.vs_system_hardware != old.vs_system_hardware)
+        {
+            view.set_vs_system_hardware(hardware);
+        }
+        if new.prg_rom_size != old.prg_rom_size {
+            view.set_prg_rom_size(new.prg_rom_size)?;
+        }
+        if new.chr_rom_size != old.chr_rom_size {
+            view.set_chr_rom_size(new.chr_rom_size)?;
+        }
+        if let Some(size) = new
+            .prg_ram_size
+            .filter(|_| new.prg_ram_size != old.prg_ram_size)
+        {
+            view.set_prg_ram_size(size)?;
+        }
+        if let Some(size) = new
+            .prg_nvram_size
+            .filter(|_| new.prg_nvram_size != old.prg_nvram_size)
+        {
+            view.set_prg_nvram_size(size)?;
+        }
+        if let Some(size) = new
+            .chr_ram_size
+            .filter(|_| new.chr_ram_size != old.chr_ram_size)
+        {
+            view.set_chr_ram_size(size)?;
+        }
+        if let Some(size) = new
+            .chr_nvram_size
+            .filter(|_| new.chr_nvram_size != old.chr_nvram_size)
+        {
+            view.set_chr_nvram_size(size)?;
+        }
+        Ok(bytes)
+    }
+
+    /// Whether both headers encode into the same bytes, unlike `==` which compares the fields
+    pub fn same_encoding(&self, other: &Header) -> Result<bool> {
+        Ok(self.encode()? == other.encode()?)
+    }
+
+    fn encode(&self) -> Result<[u8; 16]> {
+        let mut bytes = [0; 16];
+        self.clone().into_bytes(&mut bytes[..])?;
+        Ok(bytes)
+    }
+
+    pub fn from_asm<R: Read>(mut r: R) -> Result<Header> {
+        let mut source = String::new();
+        r.read_to_string(&mut source)?;
+        let bytes = import::parse_asm(&source)?;
+        Encoder::new().encode(bytes.as_slice())
+    }
+
+    pub fn into_asm<W: Write>(self, w: W, format: AsmFormat) -> Result<()> {
+        asm::write_asm(self, format, w)
+    }
+
+    pub fn into_code<W: Write>(self, w: W, format: CodeFormat) -> Result<()> {
+        code::write_code(self, format, w)
+    }
+
+    /// Rewrite ROM sizes into the given encoding
+    pub fn encode_rom_sizes(&mut self, encoding: RomSizeEncoding) -> Result<()> {
+        match encoding {
+            RomSizeEncoding::Auto => {
+                self.prg_rom_size = PrgRomSize::from_bytes(self.prg_rom_size.bytes())?;
+                self.chr_rom_size = ChrRomSize::from_bytes(self.chr_rom_size.bytes())?;
+            }
+            RomSizeEncoding::Units => {
+                self.prg_rom_size = self
+                    .prg_rom_size
+                    .to_units()
+                    .map_err(|e| anyhow!("invalid PRG-ROM size: {e}"))?;
+                self.chr_rom_size = self
+                    .chr_rom_size
+                    .to_units()
+                    .map_err(|e| anyhow!("invalid CHR-ROM size: {e}"))?;
+            }
+            // Zero size can't be written in exponent-multiplier so it's kept as is
+            RomSizeEncoding::ExponentMultiplier => {
+                if self.prg_rom_size.bytes() != 0 {
+                    self.prg_rom_size = self
+                        .prg_rom_size
+                        .to_exponent_multiplier()
+                        .map_err(|e| anyhow!("invalid PRG-ROM size: {e}"))?;
+                }
+                if self.chr_rom_size.bytes() != 0 {
+                    self.chr_rom_size = self
+                        .chr_rom_size
+                        .to_exponent_multiplier()
+                        .map_err(|e| anyhow!("invalid CHR-ROM size: {e}"))?;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns byte ranges of the sections of the file this header describes
+    pub fn layout(&self) -> Result<Layout> {
+        Layout::of(self)
+    }
+
+    /// Returns known boards which can hold the cartridge this header describes
+    pub fn matching_boards(&self) -> Result<Vec<&'static Board>> {
+        let mut boards = Vec::new();
+        for board in BOARDS {
+            if board.matches(self)? {
+                boards.push(board);
+            }
+        }
+        Ok(boards)
+    }
+
+    /// Rewrite deprecated mapper assignment into canonical one
+    pub fn modernize(&mut self) -> Option<Remapping> {
+        remap::modernize(self)
+    }
+
+    /// Rewrite canonical mapper assignment into deprecated one
+    pub fn legacy(&mut self) -> Option<Remapping> {
+        remap::legacy(self)
+    }
+
+    // Replace or annotate mapper and submapper numbers with its names
+    fn name_mapper(
+        &self,
+        fields: Map<String, Value>,
+        notation: MapperNotation,
+    ) -> Map<String, Value> {
+        let mapper = mapper_name(self.mapper);
+        let submapper = self
+            .submapper
+            .and_then(|submapper| submapper_name(self.mapper, submapper));
+        let mut named = Map::new();
+        for (key, value) in fields {
+            let name = match key.as_str() {
+                "mapper" => mapper,
+                "submapper" => submapper,
+                _ => None,
+            };
+            match (notation, name) {
+                (MapperNotation::Name, Some(name)) => {
+                    named.insert(key, name.into());
+                }
+                (MapperNotation::Annotated, Some(name)) => {
+                    let name_key = format!("{key}_name");
+                    named.insert(key, value);
+                    named.insert(name_key, name.into());
+                }
+                _ => {
+                    named.insert(key, value);
+                }
+            }
+        }
+        named
+    }
+}
+
+/// How mapper and submapper are written into definition file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MapperNotation {
+    /// Write numbers only
+    Number,
+    /// Write names in place of numbers if known
+    Name,
+    /// Write numbers along with `mapper_name` and `submapper_name` fields
+    Annotated,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_round_trip() {
+        let source = r#"{"mapper": 4, "prg_rom_size": "128K", "chr_rom_size": "128K"}"#;
+        let header = Header::from_json(source.as_bytes()).unwrap();
+        let mut json = Vec::new();
+        header
+            .clone()
+            .into_json(&mut json, MapperNotation::Number)
+            .unwrap();
+        assert_eq!(Header::from_json(json.as_slice()).unwrap(), header);
+    }
+
+    #[test]
+    fn same_encoding_ignores_representation() {
+        let header = Header::builder().mapper(4).build().unwrap();
+        let mut explicit = header.clone();
+        explicit.submapper = Some(0);
+        explicit.prg_ram_size = Some(RamSize::from_bytes(0).unwrap());
+        assert_ne!(header, explicit);
+        assert!(header.same_encoding(&explicit).unwrap());
+        explicit.submapper = Some(1);
+        assert!(!header.same_encoding(&explicit).unwrap());
+    }
+
+    #[test]
+    fn from_ines_leaves_nes2_fields_none() {
+        let header = Header::from_ines(b"NES\x1a\x08\x10\x4b\x41\0\0\0\0\0\0\0\0").unwrap();
+        assert_eq!(header.mapper, 0x44);
+        assert_eq!(header.mirroring, Some(Mirroring::FourScreens));
+        assert_eq!(header.battery, Some(true));
+        assert_eq!(header.trainer, Some(false));
+        assert_eq!(header.console, Some(Console::VsSystem));
+        assert_eq!(header.prg_rom_size.bytes(), 128 * 1024);
+        assert_eq!(header.chr_rom_size.bytes(), 128 * 1024);
+        assert_eq!(header.submapper, None);
+        assert_eq!(header.timing, None);
+        assert_eq!(header.prg_ram_size, None);
+    }
+
+    #[test]
+    fn from_ines_reads_archaic_mapper_low_bits() {
+        let header = Header::from_ines(b"NES\x1a\x02\x01\x41DiskDude!").unwrap();
+        assert_eq!(header.mapper, 4);
+        assert_eq!(header.console, None);
+        assert!(Header::from_ines(b"NES\x1a\x02\x01\x40\x08\0\0\0\0\0\0\0\0").is_err());
+    }
 }
@@ -0,0 +1,177 @@
+use super::Header;
+use super::console::Console;
+use super::decoder::Decoder;
+use super::mirroring::Mirroring;
+use super::size::{RamSize, RomSize};
+use super::timing::Timing;
+use super::vs_system::{hardware::VsSystemHardware, ppu::VsSystemPPU};
+use anyhow::{Result, bail};
+use std::io::Write;
+
+/// Assembly source format of header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AsmFormat {
+    /// `.segment "HEADER"` block of `.byte` lines
+    Ca65,
+    /// `db` lines
+    Asm6,
+    /// `.db` lines
+    Nesasm,
+    /// `.inesprg`, `.ineschr`, `.inesmap` and `.inesmir` directives
+    NesasmInes,
+}
+
+/// Write header as assembly source
+pub fn write_asm<W: Write>(header: Header, format: AsmFormat, mut w: W) -> Result<()> {
+    if format == AsmFormat::NesasmInes {
+        return write_ines_directives(&header, w);
+    }
+
+    let comments = byte_comments(&header);
+    let mut bytes = Vec::with_capacity(16);
+    Decoder::new(header).decode(&mut bytes)?;
+
+    let directive = match format {
+        AsmFormat::Ca65 => {
+            writeln!(w, ".segment \"HEADER\"")?;
+            ".byte"
+        }
+        AsmFormat::Asm6 => "db",
+        AsmFormat::Nesasm | AsmFormat::NesasmInes => ".db",
+    };
+    let line = format!("{directive} \"NES\", $1A");
+    writeln!(w, "    {line:<24}; NES 2.0 identifier")?;
+    for (byte, comment) in bytes[4..].iter().zip(comments) {
+        let line = format!("{directive} ${byte:02X}");
+        writeln!(w, "    {line:<24}; {comment}")?;
+    }
+    Ok(())
+}
+
+// Returns comments of byte 4 to byte 15
+fn byte_comments(header: &Header) -> Vec<String> {
+    let mirroring = header.mirroring.unwrap_or(Mirroring::Horizontal);
+    let console = header.console.unwrap_or(Console::Nes);
+    let timing = header.timing.unwrap_or(Timing::RP2C02);
+    let mut byte6 = format!(
+        "mapper {} bits 0-3, {} mirroring",
+        header.mapper,
+        String::from(mirroring)
+    );
+    if header.battery.unwrap_or(false) {
+        byte6.push_str(", battery");
+    }
+    if header.trainer.unwrap_or(false) {
+        byte6.push_str(", trainer");
+    }
+    let byte13 = match console {
+        Console::Nes | Console::Playchoice10 => "unused".to_string(),
+        Console::VsSystem => format!(
+            "Vs System PPU {}, hardware {}",
+            String::from(header.vs_system_ppu.unwrap_or(VsSystemPPU::Any)),
+            String::from(
+                header
+                    .vs_system_hardware
+                    .unwrap_or(VsSystemHardware::UnisystemNormal)
+            )
+        ),
+        console => format!("extended console type {}", String::from(console)),
+    };
+    vec![
+        format!("PRG-ROM {} (LSB)", describe_rom_size(header.prg_rom_size)),
+        format!("CHR-ROM {} (LSB)", describe_rom_size(header.chr_rom_size)),
+        byte6,
+        format!("mapper bits 4-7, console {}", String::from(console)),
+        format!(
+            "mapper bits 8-11, submapper {}",
+            header.submapper.unwrap_or(0)
+        ),
+        "PRG-ROM and CHR-ROM size MSB".to_string(),
+        format!(
+            "PRG-RAM {}, PRG-NVRAM {}",
+            describe_ram_size(header.prg_ram_size),
+            describe_ram_size(header.prg_nvram_size)
+        ),
+        format!(
+            "CHR-RAM {}, CHR-NVRAM {}",
+            describe_ram_size(header.chr_ram_size),
+            describe_ram_size(header.chr_nvram_size)
+        ),
+        format!("timing {}", String::from(timing)),
+        byte13,
+        "no miscellaneous ROMs".to_string(),
+        "default expansion device unspecified".to_string(),
+    ]
+}
+
+fn describe_rom_size<const UNIT: u64>(size: RomSize<UNIT>) -> String {
+    match size.exponent_multiplier() {
+        None => size.into(),
+        Some((exponent, multiplier)) => format!("2^{exponent}*{} bytes", multiplier * 2 + 1),
+    }
+}
+
+fn describe_ram_size(size: Option<RamSize>) -> String {
+    size.map_or("0K".to_string(), String::from)
+}
+
+// NESASM directives can describe iNES header only
+fn write_ines_directives<W: Write>(header: &Header, mut w: W) -> Result<()> {
+    if header.mapper > 0xFF {
+        bail!("NESASM can't express mapper {}", header.mapper);
+    } else if header.submapper.is_some_and(|s| s != 0) {
+        bail!("NESASM can't express submapper");
+    } else if header.trainer.unwrap_or(false) {
+        bail!("NESASM can't express trainer");
+    } else if header.console.is_some_and(|c| c != Console::Nes) {
+        bail!("NESASM can't express console type");
+    } else if header.timing.is_some_and(|t| t != Timing::RP2C02) {
+        bail!("NESASM can't express timing");
+    }
+    let rams = [
+        ("PRG-RAM", header.prg_ram_size),
+        ("PRG-NVRAM", header.prg_nvram_size),
+        ("CHR-RAM", header.chr_ram_size),
+        ("CHR-NVRAM", header.chr_nvram_size),
+    ];
+    for (name, size) in rams {
+        if size.is_some_and(|s| s.bytes() != 0) {
+            bail!("NESASM can't express {name} size");
+        }
+    }
+    let prg = match header.prg_rom_size.units() {
+        Some(units) if units <= 0xFF => units,
+        _ => bail!("NESASM can't express PRG-ROM size"),
+    };
+    let chr = match header.chr_rom_size.units() {
+        Some(units) if units <= 0xFF => units,
+        _ => bail!("NESASM can't express CHR-ROM size"),
+    };
+
+    let mirroring = header.mirroring.unwrap_or(Mirroring::Horizontal);
+    let battery = header.battery.unwrap_or(false);
+    let mut mir = match mirroring {
+        Mirroring::Horizontal => 0b0000,
+        Mirroring::Vertical => 0b0001,
+        Mirroring::FourScreens => 0b1000,
+    };
+    let mut mir_comment = format!("{} mirroring", String::from(mirroring));
+    if battery {
+        mir |= 0b10;
+        mir_comment.push_str(", battery");
+    }
+
+    let lines = [
+        (format!(".inesprg {prg}"), format!("{prg}x 16K PRG-ROM")),
+        (format!(".ineschr {chr}"), format!("{chr}x 8K CHR-ROM")),
+        (
+            format!(".inesmap {}", header.mapper),
+            format!("mapper {}", header.mapper),
+        ),
+        (format!(".inesmir {mir}"), mir_comment),
+    ];
+    for (line, comment) in lines {
+        writeln!(w, "    {line:<24}; {comment}")?;
+    }
+    Ok(())
+}
@@ -0,0 +1,156 @@
+use super::Header;
+use super::mirroring::Mirroring;
+use super::size::{ChrRomSize, PrgRomSize, RamSize};
+use anyhow::Result;
+
+/// Cartridge board which provides default values of header fields
+#[derive(Debug, Clone, Copy)]
+pub struct Board {
+    pub name: &'static str,
+    pub mapper: u64,
+    pub submapper: Option<u64>,
+    pub mirroring: Option<Mirroring>,
+    pub battery: bool,
+    pub prg_rom_size: &'static str,
+    pub prg_ram_size: Option<&'static str>,
+    pub prg_nvram_size: Option<&'static str>,
+    pub chr_rom_size: &'static str,
+    pub chr_ram_size: Option<&'static str>,
+}
+
+impl Board {
+    const fn new(name: &'static str, mapper: u64, prg_rom_size: &'static str) -> Self {
+        Board {
+            name,
+            mapper,
+            submapper: None,
+            mirroring: None,
+            battery: false,
+            prg_rom_size,
+            prg_ram_size: None,
+            prg_nvram_size: None,
+            chr_rom_size: "0K",
+            chr_ram_size: None,
+        }
+    }
+
+    const fn submapper(mut self, submapper: u64) -> Self {
+        self.submapper = Some(submapper);
+        self
+    }
+
+    const fn mirroring(mut self, mirroring: Mirroring) -> Self {
+        self.mirroring = Some(mirroring);
+        self
+    }
+
+    const fn chr_rom(mut self, size: &'static str) -> Self {
+        self.chr_rom_size = size;
+        self
+    }
+
+    const fn chr_ram(mut self, size: &'static str) -> Self {
+        self.chr_ram_size = Some(size);
+        self
+    }
+
+    const fn prg_ram(mut self, size: &'static str) -> Self {
+        self.prg_ram_size = Some(size);
+        self
+    }
+
+    /// Battery backed PRG-RAM
+    const fn prg_nvram(mut self, size: &'static str) -> Self {
+        self.prg_nvram_size = Some(size);
+        self.battery = true;
+        self
+    }
+
+    /// Returns true if the header describes a cartridge which fits into this board
+    pub fn matches(&self, header: &Header) -> Result<bool> {
+        if header.mapper != self.mapper
+            || self
+                .submapper
+                .is_some_and(|s| header.submapper.unwrap_or(0) != s)
+            || self.battery != header.battery.unwrap_or(false)
+        {
+            return Ok(false);
+        }
+        if let (Some(expected), Some(actual)) = (self.mirroring, header.mirroring)
+            && expected != actual
+        {
+            return Ok(false);
+        }
+        let same = |size: Option<RamSize>, expected: Option<&str>| -> Result<bool> {
+            let expected = expected.unwrap_or("0").parse::<RamSize>()?;
+            Ok(size.map_or(0, |s| s.bytes()) == expected.bytes())
+        };
+        Ok(
+            header.prg_rom_size.bytes() <= self.prg_rom_size.parse::<PrgRomSize>()?.bytes()
+                && header.chr_rom_size.bytes() <= self.chr_rom_size.parse::<ChrRomSize>()?.bytes()
+                && same(header.prg_ram_size, self.prg_ram_size)?
+                && same(header.prg_nvram_size, self.prg_nvram_size)?
+                && same(header.chr_ram_size, self.chr_ram_size)?,
+        )
+    }
+}
+
+/// Known boards, sizes are the largest ones the board supports
+pub const BOARDS: &[Board] = &[
+    Board::new("NES-NROM-128", 0, "16K").chr_rom("8K"),
+    Board::new("NES-NROM-256", 0, "32K").chr_rom("8K"),
+    Board::new("NES-SLROM", 1, "256K").chr_rom("128K"),
+    Board::new("NES-SKROM", 1, "256K")
+        .chr_rom("128K")
+        .prg_nvram("8K"),
+    Board::new("NES-SNROM", 1, "256K")
+        .chr_ram("8K")
+        .prg_nvram("8K"),
+    Board::new("NES-SUROM", 1, "512K")
+        .chr_ram("8K")
+        .prg_nvram("8K"),
+    Board::new("NES-SXROM", 1, "512K")
+        .chr_ram("8K")
+        .prg_nvram("32K"),
+    Board::new("NES-UNROM", 2, "128K").chr_ram("8K"),
+    Board::new("NES-UOROM", 2, "256K").chr_ram("8K"),
+    Board::new("NES-CNROM", 3, "32K").chr_rom("32K"),
+    Board::new("NES-TLROM", 4, "512K").chr_rom("256K"),
+    Board::new("NES-TSROM", 4, "512K")
+        .chr_rom("256K")
+        .prg_ram("8K"),
+    Board::new("NES-TKROM", 4, "512K")
+        .chr_rom("256K")
+        .prg_nvram("8K"),
+    Board::new("NES-TGROM", 4, "512K").chr_ram("8K"),
+    Board::new("NES-TNROM", 4, "512K")
+        .chr_ram("8K")
+        .prg_nvram("8K"),
+    Board::new("NES-TVROM", 4, "64K")
+        .chr_rom("64K")
+        .mirroring(Mirroring::FourScreens),
+    Board::new("NES-HKROM", 4, "512K")
+        .submapper(1)
+        .chr_rom("256K")
+        .prg_nvram("1K"),
+    Board::new("NES-ELROM", 5, "1M").chr_rom("1M"),
+    Board::new("NES-EKROM", 5, "1M")
+        .chr_rom("1M")
+        .prg_nvram("8K"),
+    Board::new("NES-ANROM", 7, "128K").chr_ram("8K"),
+    Board::new("NES-AOROM", 7, "256K").chr_ram("8K"),
+    Board::new("NES-PNROM", 9, "128K").chr_rom("128K"),
+    Board::new("NES-FKROM", 10, "128K")
+        .chr_rom("128K")
+        .prg_nvram("8K"),
+    Board::new("NES-CPROM", 13, "32K").chr_ram("16K"),
+    Board::new("NES-BNROM", 34, "128K")
+        .submapper(2)
+        .chr_ram("8K"),
+    Board::new("NES-GNROM", 66, "128K").chr_rom("32K"),
+];
+
+/// Returns board which has the given name
+pub fn board_from_name(name: &str) -> Option<&'static Board> {
+    BOARDS.iter().find(|b| b.name.eq_ignore_ascii_case(name))
+}
@@ -0,0 +1,130 @@
+use super::Header;
+use super::console::Console;
+use super::decoder::Decoder;
+use super::diagnostic::FieldError;
+use super::mirroring::Mirroring;
+use super::size::{ChrRomSize, PrgRomSize, RamSize};
+use super::timing::Timing;
+use super::vs_system::{hardware::VsSystemHardware, ppu::VsSystemPPU};
+use anyhow::Result;
+use std::io;
+
+/// Builder of `Header`, whose sizes are given in bytes and validated on `build`
+///
+/// Sizes accept plain bytes as well as `KiB` and `MiB`, like
+/// `Header::builder().mapper(4).prg_rom(KiB(128)).battery(true).build()`.
+#[derive(Debug, Clone, Default)]
+pub struct HeaderBuilder {
+    header: Header,
+    prg_rom: u64,
+    chr_rom: u64,
+    prg_ram: Option<u64>,
+    prg_nvram: Option<u64>,
+    chr_ram: Option<u64>,
+    chr_nvram: Option<u64>,
+}
+
+impl HeaderBuilder {
+    /// Create a builder of mapper 0 without ROMs
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn mapper(mut self, mapper: u64) -> Self {
+        self.header.mapper = mapper;
+        self
+    }
+
+    pub fn submapper(mut self, submapper: u64) -> Self {
+        self.header.submapper = Some(submapper);
+        self
+    }
+
+    pub fn mirroring(mut self, mirroring: Mirroring) -> Self {
+        self.header.mirroring = Some(mirroring);
+        self
+    }
+
+    pub fn battery(mut self, battery: bool) -> Self {
+        self.header.battery = Some(battery);
+        self
+    }
+
+    pub fn trainer(mut self, trainer: bool) -> Self {
+        self.header.trainer = Some(trainer);
+        self
+    }
+
+    pub fn console(mut self, console: Console) -> Self {
+        self.header.console = Some(console);
+        self
+    }
+
+    pub fn timing(mut self, timing: Timing) -> Self {
+        self.header.timing = Some(timing);
+        self
+    }
+
+    pub fn vs_system_ppu(mut self, ppu: VsSystemPPU) -> Self {
+        self.header.vs_system_ppu = Some(ppu);
+        self
+    }
+
+    pub fn vs_system_hardware(mut self, hardware: VsSystemHardware) -> Self {
+        self.header.vs_system_hardware = Some(hardware);
+        self
+    }
+
+    pub fn prg_rom(mut self, size: impl Into<u64>) -> Self {
+        self.prg_rom = size.into();
+        self
+    }
+
+    pub fn chr_rom(mut self, size: impl Into<u64>) -> Self {
+        self.chr_rom = size.into();
+        self
+    }
+
+    pub fn prg_ram(mut self, size: impl Into<u64>) -> Self {
+        self.prg_ram = Some(size.into());
+        self
+    }
+
+    pub fn prg_nvram(mut self, size: impl Into<u64>) -> Self {
+        self.prg_nvram = Some(size.into());
+        self
+    }
+
+    pub fn chr_ram(mut self, size: impl Into<u64>) -> Self {
+        self.chr_ram = Some(size.into());
+        self
+    }
+
+    pub fn chr_nvram(mut self, size: impl Into<u64>) -> Self {
+        self.chr_nvram = Some(size.into());
+        self
+    }
+
+    /// Returns the header after checking that every field can be written into NES 2.0 header
+    pub fn build(self) -> Result<Header> {
+        let ram_size = |size: Option<u64>, field| {
+            let size = size.map(RamSize::from_bytes).transpose();
+            size.map_err(|e| FieldError::new(field, e))
+        };
+        let header = Header {
+            prg_rom_size: PrgRomSize::from_bytes(self.prg_rom)
+                .map_err(|e| FieldError::new("prg_rom_size", e))?,
+            chr_rom_size: ChrRomSize::from_bytes(self.chr_rom)
+                .map_err(|e| FieldError::new("chr_rom_size", e))?,
+            prg_ram_size: ram_size(self.prg_ram, "prg_ram_size")?,
+            prg_nvram_size: ram_size(self.prg_nvram, "prg_nvram_size")?,
+            chr_ram_size: ram_size(self.chr_ram, "chr_ram_size")?,
+            chr_nvram_size: ram_size(self.chr_nvram, "chr_nvram_size")?,
+            ..self.header
+        };
+
+        // Validated by the same rules as writing the header
+        Decoder::new(header.clone()).decode(io::sink())?;
+        Ok(header)
+    }
+}
@@ -0,0 +1,73 @@
+use super::Header;
+use super::decoder::Decoder;
+use super::mirroring::Mirroring;
+use super::size::RamSize;
+use anyhow::Result;
+use std::io::Write;
+
+/// Programming language of generated constants
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodeFormat {
+    /// `static const uint8_t header[16]` with `#define` constants
+    C,
+    /// `pub const HEADER: [u8; 16]` with `pub const` constants
+    Rust,
+}
+
+/// Write header bytes and constants of its fields as source code
+pub fn write_code<W: Write>(header: Header, format: CodeFormat, mut w: W) -> Result<()> {
+    let ram_size = |size: Option<RamSize>| size.map_or(0, |s| s.bytes());
+    let mirroring = header.mirroring.unwrap_or(Mirroring::Horizontal);
+    let numbers = [
+        ("MAPPER", "u16", header.mapper),
+        ("SUBMAPPER", "u8", header.submapper.unwrap_or(0)),
+        ("PRG_ROM_SIZE", "u64", header.prg_rom_size.bytes()),
+        ("PRG_RAM_SIZE", "u64", ram_size(header.prg_ram_size)),
+        ("PRG_NVRAM_SIZE", "u64", ram_size(header.prg_nvram_size)),
+        ("CHR_ROM_SIZE", "u64", header.chr_rom_size.bytes()),
+        ("CHR_RAM_SIZE", "u64", ram_size(header.chr_ram_size)),
+        ("CHR_NVRAM_SIZE", "u64", ram_size(header.chr_nvram_size)),
+    ];
+    let flags = [
+        ("BATTERY", header.battery.unwrap_or(false)),
+        ("TRAINER", header.trainer.unwrap_or(false)),
+        ("VERTICAL_MIRRORING", mirroring == Mirroring::Vertical),
+        ("FOUR_SCREENS", mirroring == Mirroring::FourScreens),
+    ];
+    let mut bytes = Vec::with_capacity(16);
+    Decoder::new(header).decode(&mut bytes)?;
+    let bytes = bytes
+        .iter()
+        .map(|b| format!("0x{b:02X}"))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    match format {
+        CodeFormat::C => {
+            writeln!(w, "/* Generated by neshdr */")?;
+            writeln!(w, "#include <stdint.h>")?;
+            writeln!(w)?;
+            for (name, _, value) in numbers {
+                writeln!(w, "#define NES_HEADER_{name} {value}")?;
+            }
+            for (name, flag) in flags {
+                writeln!(w, "#define NES_HEADER_{name} {}", flag as u8)?;
+            }
+            writeln!(w)?;
+            writeln!(w, "static const uint8_t header[16] = {{ {bytes} }};")?;
+        }
+        CodeFormat::Rust => {
+            writeln!(w, "// Generated by neshdr")?;
+            writeln!(w)?;
+            for (name, ty, value) in numbers {
+                writeln!(w, "pub const {name}: {ty} = {value};")?;
+            }
+            for (name, flag) in flags {
+                writeln!(w, "pub const {name}: bool = {flag};")?;
+            }
+            writeln!(w)?;
+            writeln!(w, "pub const HEADER: [u8; 16] = [{bytes}];")?;
+        }
+    }
+    Ok(())
+}
@@ -1,7 +1,9 @@
-use anyhow::{Error, bail};
+use super::diagnostic::did_you_mean;
+use super::name::{from_name, names};
+use anyhow::{Error, anyhow};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(try_from = "String", into = "String")]
 pub enum Console {
     Nes,
@@ -22,29 +24,45 @@ pub enum Console {
     Reserved0F,
 }
 
+impl Console {
+    /// All variants in declaration order
+    pub const ALL: [Console; 16] = [
+        Console::Nes,
+        Console::VsSystem,
+        Console::Playchoice10,
+        Console::NesWithDecimal,
+        Console::NesWithEPSM,
+        Console::VT01,
+        Console::VT02,
+        Console::VT03,
+        Console::VT09,
+        Console::VT32,
+        Console::VT369,
+        Console::UM6539,
+        Console::FamicomNetworkSystem,
+        Console::Reserved0D,
+        Console::Reserved0E,
+        Console::Reserved0F,
+    ];
+
+    /// Alternative names accepted in addition to the canonical ones
+    pub const ALIASES: [(&str, Console); 3] = [
+        ("VS", Console::VsSystem),
+        ("PC10", Console::Playchoice10),
+        ("Famiclone-VT02", Console::VT02),
+    ];
+}
+
 impl TryFrom<String> for Console {
     type Error = Error;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        match value.as_str() {
-            "NES" => Ok(Console::Nes),
-            "Vs System" => Ok(Console::VsSystem),
-            "Playchoice 10" => Ok(Console::Playchoice10),
-            "NES With Decimal" => Ok(Console::NesWithDecimal),
-            "NES With EPSM" => Ok(Console::NesWithEPSM),
-            "VT01" => Ok(Console::VT01),
-            "VT02" => Ok(Console::VT02),
-            "VT03" => Ok(Console::VT03),
-            "VT09" => Ok(Console::VT09),
-            "VT32" => Ok(Console::VT32),
-            "VT369" => Ok(Console::VT369),
-            "UM6539" => Ok(Console::UM6539),
-            "Famicom Network System" => Ok(Console::FamicomNetworkSystem),
-            "Reserved0D" => Ok(Console::Reserved0D),
-            "Reserved0E" => Ok(Console::Reserved0E),
-            "Reserved0F" => Ok(Console::Reserved0F),
-            _ => bail!("invalid console name: {value}"),
-        }
+        from_name(&value, &Console::ALL, &Console::ALIASES).ok_or_else(|| {
+            anyhow!(
+                "invalid console name: {value}{}",
+                did_you_mean(&value, names(&Console::ALL, &Console::ALIASES))
+            )
+        })
     }
 }
 
@@ -71,3 +89,39 @@ impl From<Console> for String {
         value.to_string()
     }
 }
+
+/// Discriminant written in the header, which is defined in `codec::variants`
+impl From<Console> for u8 {
+    fn from(value: Console) -> Self {
+        use crate::codec::variants::console;
+        match value {
+            Console::Nes => console::Nes,
+            Console::VsSystem => console::VsSystem,
+            Console::Playchoice10 => console::Playchoice10,
+            Console::NesWithDecimal => console::NesWithDecimal,
+            Console::NesWithEPSM => console::NesWithEPSM,
+            Console::VT01 => console::VT01,
+            Console::VT02 => console::VT02,
+            Console::VT03 => console::VT03,
+            Console::VT09 => console::VT09,
+            Console::VT32 => console::VT32,
+            Console::VT369 => console::VT369,
+            Console::UM6539 => console::UM6539,
+            Console::FamicomNetworkSystem => console::FamicomNetworkSystem,
+            Console::Reserved0D => console::Reserved0D,
+            Console::Reserved0E => console::Reserved0E,
+            Console::Reserved0F => console::Reserved0F,
+        }
+    }
+}
+
+impl TryFrom<u8> for Console {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Console::ALL
+            .into_iter()
+            .find(|variant| u8::from(*variant) == value)
+            .ok_or_else(|| anyhow!("invalid console type: {value}"))
+    }
+}
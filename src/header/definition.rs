@@ -0,0 +1,160 @@
+use super::Header;
+use super::board::{BOARDS, board_from_name};
+use super::diagnostic::{FieldError, did_you_mean};
+use super::mapper::{mapper_from_name, mapper_names, submapper_from_name, submapper_names};
+use super::mirroring::Mirroring;
+use super::size::{ChrRomSize, PrgRomSize, RamSize, RomSizeNotation};
+use anyhow::{Context, Error, Result, bail};
+use serde::Deserialize;
+
+/// Number which can also be written as a name in definition file
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum NumberOrName {
+    Number(u64),
+    Name(String),
+}
+
+/// Header definition as written by user, resolved into `Header`
+#[derive(Deserialize, Debug, Clone)]
+pub struct Definition {
+    pub board: Option<String>,
+    pub mapper: Option<NumberOrName>,
+    pub submapper: Option<NumberOrName>,
+    pub mirroring: Option<String>,
+    pub battery: Option<bool>,
+    pub trainer: Option<bool>,
+    pub console: Option<String>,
+    pub timing: Option<String>,
+    pub vs_system_ppu: Option<String>,
+    pub vs_system_hardware: Option<String>,
+    pub prg_rom_size: Option<RomSizeNotation>,
+    pub prg_ram_size: Option<String>,
+    pub prg_nvram_size: Option<String>,
+    pub chr_rom_size: Option<RomSizeNotation>,
+    pub chr_ram_size: Option<String>,
+    pub chr_nvram_size: Option<String>,
+}
+
+impl TryFrom<Definition> for Header {
+    type Error = Error;
+
+    fn try_from(value: Definition) -> Result<Self, Self::Error> {
+        let board = match value.board {
+            Some(ref name) => match board_from_name(name) {
+                Some(board) => Some(board),
+                None => bail!(FieldError::new(
+                    "board",
+                    format!(
+                        "invalid board name: {name}{}",
+                        did_you_mean(name, BOARDS.iter().map(|b| b.name))
+                    )
+                )),
+            },
+            None => None,
+        };
+
+        // Explicitly written fields take precedence over the board's ones
+        let mapper = match value.mapper {
+            Some(mapper) => resolve_mapper(mapper)?,
+            None => board.map(|b| b.mapper).context("mapper is not specified")?,
+        };
+        // Board's submapper is meaningless for another mapper
+        let submapper = match value.submapper {
+            Some(submapper) => Some(resolve_submapper(mapper, submapper)?),
+            None => board
+                .filter(|b| b.mapper == mapper)
+                .and_then(|b| b.submapper),
+        };
+        let prg_rom_size = value
+            .prg_rom_size
+            .or(board.map(|b| RomSizeNotation::Literal(b.prg_rom_size.into())))
+            .context("prg_rom_size is not specified")?;
+        let chr_rom_size = value
+            .chr_rom_size
+            .or(board.map(|b| RomSizeNotation::Literal(b.chr_rom_size.into())))
+            .context("chr_rom_size is not specified")?;
+        let ram_size = |size: &Option<String>, preset: Option<&str>, field, name: &str| {
+            let size = size.as_deref().or(preset);
+            let size = size.map(|size| RamSize::parse_as(size, name)).transpose();
+            size.map_err(|e| FieldError::new(field, e))
+        };
+
+        Ok(Header {
+            mapper,
+            submapper,
+            mirroring: resolve_name::<Mirroring>(value.mirroring, "mirroring")?
+                .or(board.and_then(|b| b.mirroring)),
+            battery: value.battery.or(board.map(|b| b.battery)),
+            trainer: value.trainer,
+            console: resolve_name(value.console, "console")?,
+            timing: resolve_name(value.timing, "timing")?,
+            vs_system_ppu: resolve_name(value.vs_system_ppu, "vs_system_ppu")?,
+            vs_system_hardware: resolve_name(value.vs_system_hardware, "vs_system_hardware")?,
+            prg_rom_size: PrgRomSize::from_notation(prg_rom_size, "PRG-ROM")
+                .map_err(|e| FieldError::new("prg_rom_size", e))?,
+            prg_ram_size: ram_size(
+                &value.prg_ram_size,
+                board.and_then(|b| b.prg_ram_size),
+                "prg_ram_size",
+                "PRG-RAM",
+            )?,
+            prg_nvram_size: ram_size(
+                &value.prg_nvram_size,
+                board.and_then(|b| b.prg_nvram_size),
+                "prg_nvram_size",
+                "PRG-NVRAM",
+            )?,
+            chr_rom_size: ChrRomSize::from_notation(chr_rom_size, "CHR-ROM")
+                .map_err(|e| FieldError::new("chr_rom_size", e))?,
+            chr_ram_size: ram_size(
+                &value.chr_ram_size,
+                board.and_then(|b| b.chr_ram_size),
+                "chr_ram_size",
+                "CHR-RAM",
+            )?,
+            chr_nvram_size: ram_size(&value.chr_nvram_size, None, "chr_nvram_size", "CHR-NVRAM")?,
+        })
+    }
+}
+
+// Names are resolved here rather than by serde, which loses the field of the error
+fn resolve_name<T: TryFrom<String, Error = Error>>(
+    name: Option<String>,
+    field: &'static str,
+) -> Result<Option<T>> {
+    let value = name.map(T::try_from).transpose();
+    value.map_err(|e| FieldError::new(field, e).into())
+}
+
+fn resolve_mapper(mapper: NumberOrName) -> Result<u64> {
+    match mapper {
+        NumberOrName::Number(number) => Ok(number),
+        NumberOrName::Name(name) => match mapper_from_name(&name) {
+            Some(number) => Ok(number),
+            None => bail!(FieldError::new(
+                "mapper",
+                format!(
+                    "invalid mapper name: {name}{}",
+                    did_you_mean(&name, mapper_names())
+                )
+            )),
+        },
+    }
+}
+
+fn resolve_submapper(mapper: u64, submapper: NumberOrName) -> Result<u64> {
+    match submapper {
+        NumberOrName::Number(number) => Ok(number),
+        NumberOrName::Name(name) => match submapper_from_name(mapper, &name) {
+            Some(number) => Ok(number),
+            None => bail!(FieldError::new(
+                "submapper",
+                format!(
+                    "invalid submapper name for mapper {mapper}: {name}{}",
+                    did_you_mean(&name, submapper_names(mapper))
+                )
+            )),
+        },
+    }
+}
@@ -0,0 +1,330 @@
+use anyhow::Error;
+use serde::Serialize;
+use std::fmt::{self, Display, Formatter};
+
+/// Error of a field of definition, which is located in source by its key
+#[derive(Debug)]
+pub struct FieldError {
+    pub field: &'static str,
+    pub message: String,
+}
+
+impl FieldError {
+    pub fn new(field: &'static str, message: impl Display) -> Self {
+        FieldError {
+            field,
+            message: message.to_string(),
+        }
+    }
+}
+
+impl Display for FieldError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+impl std::error::Error for FieldError {}
+
+/// Error pointing to a span of definition source
+#[derive(Serialize, Debug)]
+pub struct Diagnostic {
+    pub message: String,
+    pub file: String,
+    /// 1-based line of the span
+    pub line: Option<usize>,
+    /// 1-based column of the span, counted in characters
+    pub column: Option<usize>,
+    /// Length of the span in characters
+    pub length: Option<usize>,
+    #[serde(skip)]
+    snippet: Option<String>,
+}
+
+impl Diagnostic {
+    /// Diagnostic of the file without location
+    pub fn new(file: &str, message: impl Display) -> Self {
+        Diagnostic {
+            message: message.to_string(),
+            file: file.to_string(),
+            line: None,
+            column: None,
+            length: None,
+            snippet: None,
+        }
+    }
+
+    /// Locate error of parsing or encoding the definition source of the file
+    pub fn locate(file: &str, source: &str, error: &Error) -> Self {
+        let (message, offset) = match locate_error(source, error) {
+            Some((message, offset)) => (message, Some(offset)),
+            None => (error.to_string(), None),
+        };
+        let mut diagnostic = Diagnostic::new(file, message);
+        if let Some(offset) = offset {
+            let offset = floor_char_boundary(source, offset);
+            let line_start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = source[offset..]
+                .find('\n')
+                .map_or(source.len(), |i| offset + i);
+            let line = source[line_start..line_end].trim_end_matches('\r');
+            let column = source[line_start..offset].chars().count();
+            diagnostic.line = Some(source[..line_start].matches('\n').count() + 1);
+            diagnostic.column = Some(column + 1);
+            diagnostic.length = Some(token_length(&source[offset..line_end]));
+            diagnostic.snippet = Some(line.to_string());
+        }
+        diagnostic
+    }
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}", self.message)?;
+        let (Some(line), Some(column), Some(snippet)) = (self.line, self.column, &self.snippet)
+        else {
+            return write!(f, " --> {}", self.file);
+        };
+        let gutter = " ".repeat(line.to_string().len());
+        writeln!(f, "{gutter}--> {}:{line}:{column}", self.file)?;
+        writeln!(f, "{gutter} |")?;
+        writeln!(f, "{line} | {snippet}")?;
+        write!(
+            f,
+            "{gutter} | {}{}",
+            " ".repeat(column - 1),
+            "^".repeat(self.length.unwrap_or(1).max(1))
+        )
+    }
+}
+
+impl std::error::Error for Diagnostic {}
+
+/// Returns " did you mean '...'?" suffix for the candidate closest to the misspelled value
+pub fn did_you_mean<S: AsRef<str>>(value: &str, candidates: impl IntoIterator<Item = S>) -> String {
+    let value = value.to_lowercase();
+    let threshold = (value.chars().count() / 3).max(2);
+    candidates
+        .into_iter()
+        .map(|c| (edit_distance(&value, &c.as_ref().to_lowercase()), c))
+        .filter(|(distance, _)| *distance <= threshold)
+        .min_by_key(|(distance, _)| *distance)
+        .map_or(String::new(), |(_, c)| {
+            format!("; did you mean '{}'?", c.as_ref())
+        })
+}
+
+// Levenshtein distance counted in characters
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b = b.chars().collect::<Vec<_>>();
+    let mut row = (0..=b.len()).collect::<Vec<_>>();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + (ca != *cb) as usize;
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+// Returns message without location and byte offset of the error in the source
+fn locate_error(source: &str, error: &Error) -> Option<(String, usize)> {
+    if let Some(e) = error.downcast_ref::<FieldError>() {
+        return Some((e.message.clone(), find_field_value(source, e.field)?));
+    }
+    if let Some(e) = error.downcast_ref::<serde_json::Error>() {
+        let message = strip_location(&e.to_string(), e.line(), e.column());
+        let offset = offset_of(source, e.line(), e.column())?;
+        return match e.classify() {
+            serde_json::error::Category::Data => Some((message, start_of_token(source, offset))),
+            _ => Some((message, offset)),
+        };
+    }
+    if let Some(e) = error.downcast_ref::<serde_yaml::Error>() {
+        let location = e.location()?;
+        let message = strip_location(&e.to_string(), location.line(), location.column());
+        return Some((message, location.index()));
+    }
+    if let Some(e) = error.downcast_ref::<toml::de::Error>() {
+        return Some((e.message().trim().to_string(), e.span()?.start));
+    }
+    if let Some(json5::Error::Message { msg, location }) = error.downcast_ref::<json5::Error>() {
+        let location = location.as_ref()?;
+        return Some((
+            msg.clone(),
+            offset_of(source, location.line, location.column)?,
+        ));
+    }
+    if let Some(e) = error.downcast_ref::<ron::error::SpannedError>() {
+        let start = offset_of(source, e.span.start.line, e.span.start.col)?;
+        return Some((e.code.to_string(), start));
+    }
+    None
+}
+
+// serde_json and serde_yaml append " at line L column C" to their messages
+fn strip_location(message: &str, line: usize, column: usize) -> String {
+    let suffix = format!(" at line {line} column {column}");
+    message.strip_suffix(&suffix).unwrap_or(message).to_string()
+}
+
+// Byte offset of 1-based line and column
+fn offset_of(source: &str, line: usize, column: usize) -> Option<usize> {
+    let line_start = match line {
+        0 => return None,
+        1 => 0,
+        _ => source.match_indices('\n').nth(line - 2)?.0 + 1,
+    };
+    let line = &source[line_start..];
+    let column = line
+        .char_indices()
+        .nth(column.saturating_sub(1))
+        .map_or(line.len(), |(i, _)| i);
+    Some(line_start + column)
+}
+
+// serde_json reports the position just after the invalid value, so go back to its start
+fn start_of_token(source: &str, end: usize) -> usize {
+    let end = floor_char_boundary(source, end);
+    let before = &source[..end];
+    if let Some(string) = before.strip_suffix('"') {
+        return string.rfind('"').unwrap_or(end);
+    }
+    let start = before
+        .rfind(|c: char| c.is_whitespace() || matches!(c, ':' | ',' | '[' | '{'))
+        .map_or(0, |i| i + 1);
+    if start == end {
+        end.saturating_sub(1)
+    } else {
+        start
+    }
+}
+
+/// Byte offset of the value of the first key with the field name outside of comments
+pub fn find_field_value(source: &str, field: &str) -> Option<usize> {
+    let is_ident = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    for (start, _) in source.match_indices(field) {
+        let line = &source[source[..start].rfind('\n').map_or(0, |i| i + 1)..start];
+        if source[..start].ends_with(is_ident) || !is_key_position(line) {
+            continue;
+        }
+        let rest = &source[start + field.len()..];
+        let rest = rest.trim_start_matches(['"', '\'']).trim_start();
+        let Some(value) = rest.strip_prefix([':', '=']) else {
+            continue;
+        };
+        let value = value.trim_start();
+        return Some(source.len() - value.len());
+    }
+    None
+}
+
+// Length of the value starting at the beginning of the line in characters
+// Whether the end of the line can start a key, which is outside of comments and strings except
+// just after the opening quote of a quoted key
+fn is_key_position(line: &str) -> bool {
+    let mut quote = None;
+    let mut chars = line.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (Some(_), '\\') => {
+                chars.next();
+            }
+            (Some((q, _)), c) if c == q => quote = None,
+            (Some(_), _) => (),
+            (None, '"' | '\'') => quote = Some((c, i)),
+            (None, '#') => return false,
+            (None, '/') if chars.peek().is_some_and(|(_, c)| *c == '/') => return false,
+            _ => (),
+        }
+    }
+    quote.is_none_or(|(_, start)| start + 1 == line.len())
+}
+
+fn token_length(line: &str) -> usize {
+    let mut chars = line.chars();
+    match chars.next() {
+        Some(quote @ ('"' | '\'')) => match chars.position(|c| c == quote) {
+            Some(i) => i + 2,
+            None => line.chars().count(),
+        },
+        // Unquoted value ends at the delimiter of flow collection, or the end of line
+        _ => {
+            let end = line.find([',', '}', ')', ']']).unwrap_or(line.len());
+            line[..end].trim_end().chars().count()
+        }
+    }
+}
+
+fn floor_char_boundary(source: &str, offset: usize) -> usize {
+    (0..=offset.min(source.len()))
+        .rev()
+        .find(|i| source.is_char_boundary(*i))
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::Header;
+    use crate::header::format::DefinitionFormat;
+
+    fn locate(source: &str, format: DefinitionFormat) -> Diagnostic {
+        let error = Header::from_definition(source.as_bytes(), format).unwrap_err();
+        Diagnostic::locate("header.json", source, &error)
+    }
+
+    #[test]
+    fn locates_field_error() {
+        let source =
+            "{\n  \"mapper\": 4,\n  \"prg_rom_size\": \"33K\",\n  \"chr_rom_size\": \"0\"\n}";
+        let diagnostic = locate(source, DefinitionFormat::Json);
+        assert_eq!(diagnostic.line, Some(3));
+        assert_eq!(diagnostic.column, Some(19));
+        assert_eq!(diagnostic.length, Some(5));
+        assert_eq!(
+            diagnostic.to_string(),
+            "33K PRG-ROM is neither a multiple of 16K nor exponent-multiplier; nearest: 32K, 40960\n \
+             --> header.json:3:19\n  |\n3 |   \"prg_rom_size\": \"33K\",\n  |                   ^^^^^"
+        );
+    }
+
+    #[test]
+    fn locates_syntax_error() {
+        let diagnostic = locate("{\"mapper\": 4,,}", DefinitionFormat::Json);
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(1), Some(14)));
+        assert!(!diagnostic.message.contains("line 1"));
+    }
+
+    #[test]
+    fn counts_columns_in_characters() {
+        let source =
+            "# ヘッダー\nmapper = \"MMC9\" # 説明\nprg_rom_size = \"32K\"\nchr_rom_size = \"8K\"\n";
+        let diagnostic = locate(source, DefinitionFormat::Toml);
+        assert_eq!((diagnostic.line, diagnostic.column), (Some(2), Some(10)));
+        assert_eq!(diagnostic.length, Some(6));
+        assert!(diagnostic.message.ends_with("did you mean 'MMC1'?"));
+    }
+
+    #[test]
+    fn skips_keys_in_comments() {
+        let source = "// \"mapper\": 1\n{\"note\": \"# mapper: 2\", \"mapper\": 3}";
+        assert_eq!(find_field_value(source, "mapper"), Some(source.len() - 2));
+        assert_eq!(find_field_value("# mapper = 1", "mapper"), None);
+        assert_eq!(find_field_value("submapper = 1", "mapper"), None);
+    }
+
+    #[test]
+    fn suggests_closest_candidate() {
+        let candidates = ["Horizontal", "Vertical", "FourScreens"];
+        assert_eq!(
+            did_you_mean("vertcal", candidates),
+            "; did you mean 'Vertical'?"
+        );
+        assert_eq!(did_you_mean("diagonal", candidates), "");
+        assert_eq!(edit_distance("kitten", "sitting"), 3);
+    }
+}
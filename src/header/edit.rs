@@ -0,0 +1,314 @@
+//! Edit of definition source in place, which keeps comments and layout of the other fields.
+
+use super::Header;
+use super::definition::Definition;
+use super::diagnostic::find_field_value;
+use super::format::DefinitionFormat;
+use super::schema::definition_schema;
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::Value;
+
+/// Parse `<field>=<value>`, typing the value as the schema of the field expects
+pub fn parse_assignment(assignment: &str) -> Result<(String, Value)> {
+    let (field, value) = assignment
+        .split_once('=')
+        .with_context(|| format!("expected <field>=<value>: {assignment}"))?;
+    let (field, value) = (field.trim(), value.trim());
+    let schema = definition_schema();
+    let Some(property) = schema["properties"].get(field) else {
+        let fields = schema["properties"].as_object().into_iter().flatten();
+        bail!(
+            "unknown field: {field}{}",
+            super::diagnostic::did_you_mean(field, fields.map(|(name, _)| name))
+        );
+    };
+    let accepts = |kind: &str| {
+        property["type"] == kind
+            || property["oneOf"]
+                .as_array()
+                .is_some_and(|types| types.iter().any(|t| t["type"] == kind))
+    };
+    let value = if accepts("boolean") {
+        Value::Bool(
+            value
+                .parse()
+                .map_err(|_| anyhow!("{field} must be true or false: {value}"))?,
+        )
+    } else if let Some(number) = accepts("integer").then(|| parse_integer(value)).flatten() {
+        Value::from(number)
+    } else {
+        Value::from(value)
+    };
+    Ok((field.to_string(), value))
+}
+
+fn parse_integer(value: &str) -> Option<u64> {
+    match value.strip_prefix("0x").or(value.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16).ok(),
+        None => value.parse().ok(),
+    }
+}
+
+/// Set the fields of the header as if they were written in its definition
+pub fn set_header_fields(header: &Header, fields: &[(String, Value)]) -> Result<Header> {
+    let Value::Object(mut definition) = serde_json::to_value(header)? else {
+        bail!("header isn't serialized into an object");
+    };
+    for (field, value) in fields {
+        definition.insert(field.clone(), value.clone());
+    }
+    let definition: Definition = serde_json::from_value(Value::Object(definition))?;
+    Header::try_from(definition)
+}
+
+/// Replace values of the fields in definition source, adding the fields which aren't written.
+///
+/// Other text is kept as is, so the result should be checked to be a valid definition.
+pub fn set_fields(source: &str, format: DefinitionFormat, fields: &[(String, Value)]) -> String {
+    let mut source = source.to_string();
+    for (field, value) in fields {
+        // JSON literal of a string, number or boolean is also valid in the other formats
+        let value = value.to_string();
+        source = match find_field_value(&source, field) {
+            Some(start) => replace_value(&source, start, format, &value),
+            None => insert(&source, format, field, &value),
+        };
+    }
+    source
+}
+
+// Replace the value starting at the offset
+fn replace_value(source: &str, start: usize, format: DefinitionFormat, value: &str) -> String {
+    // YAML mapping in block style starts on the next line, and lasts while lines are indented
+    let key_end = source[..start].trim_end().len();
+    if format == DefinitionFormat::Yaml && source[key_end..start].contains('\n') {
+        let indent = indentation(&source[line_start(source, key_end)..]);
+        let mut end = start;
+        let mut offset = line_start(source, start);
+        for line in source[offset..].split_inclusive('\n') {
+            if !line.trim().is_empty() && indentation(line) <= indent {
+                break;
+            }
+            offset += line.len();
+            end = offset;
+        }
+        let end = key_end + source[key_end..end].trim_end().len();
+        return format!("{} {value}{}", &source[..key_end], &source[end..]);
+    }
+    let end = start + value_length(&source[start..]);
+    format!("{}{value}{}", &source[..start], &source[end..])
+}
+
+// Length of the value at the start of the text in bytes, which is a quoted string,
+// a bracketed collection, or a scalar ending at a delimiter or comment
+fn value_length(text: &str) -> usize {
+    let mut chars = text.char_indices();
+    match chars.next() {
+        Some((_, quote @ ('"' | '\''))) => {
+            while let Some((i, c)) = chars.next() {
+                match c {
+                    '\\' => {
+                        chars.next();
+                    }
+                    c if c == quote => return i + 1,
+                    _ => (),
+                }
+            }
+            text.len()
+        }
+        Some((_, '{' | '(' | '[')) => {
+            let mut depth = 0;
+            let mut quote = None;
+            for (i, c) in text.char_indices() {
+                match (quote, c) {
+                    (Some(q), c) if c == q => quote = None,
+                    (Some(_), _) => (),
+                    (None, '"' | '\'') => quote = Some(c),
+                    (None, '{' | '(' | '[') => depth += 1,
+                    (None, '}' | ')' | ']') => {
+                        depth -= 1;
+                        if depth == 0 {
+                            return i + 1;
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            text.len()
+        }
+        _ => {
+            let end = text
+                .find([',', '}', ')', ']', '\n', '#'])
+                .unwrap_or(text.len());
+            let end = text[..end].find("//").unwrap_or(end);
+            text[..end].trim_end().len()
+        }
+    }
+}
+
+// Add the field which is not written, before the other fields in JSON, JSON5 and RON
+fn insert(source: &str, format: DefinitionFormat, field: &str, value: &str) -> String {
+    match format {
+        DefinitionFormat::Toml | DefinitionFormat::Yaml => {
+            let line = match format {
+                DefinitionFormat::Toml => format!("{field} = {value}\n"),
+                _ => format!("{field}: {value}\n"),
+            };
+            // Keys after a table header of TOML would belong to the table
+            let at = match format {
+                DefinitionFormat::Toml => source
+                    .match_indices('[')
+                    .map(|(i, _)| i)
+                    .find(|&i| source[line_start(source, i)..i].trim().is_empty()),
+                _ => None,
+            };
+            // Blank lines before the table are kept between the fields and the table
+            match at.map(|at| source[..at].trim_end().len()) {
+                Some(0) => format!("{line}{source}"),
+                Some(at) => format!("{}\n{}{}", &source[..at], line.trim_end(), &source[at..]),
+                None if source.is_empty() || source.ends_with('\n') => format!("{source}{line}"),
+                None => format!("{source}\n{line}"),
+            }
+        }
+        DefinitionFormat::Json | DefinitionFormat::Json5 | DefinitionFormat::Ron => {
+            let key = match format {
+                DefinitionFormat::Ron => field.to_string(),
+                _ => format!("\"{field}\""),
+            };
+            let Some(open) = skip_trivia(source, 0)
+                .and_then(|i| source[i..].find(['{', '(']).map(|open| i + open))
+            else {
+                return format!("{{{key}: {value}}}");
+            };
+            let next = skip_trivia(source, open + 1).unwrap_or(source.len());
+            let comma = match source[next..].starts_with(['}', ')']) {
+                true => "",
+                false => ",",
+            };
+            let field = match source[open + 1..next].contains('\n') {
+                true => {
+                    let indent = indentation(&source[line_start(source, next)..]);
+                    format!(
+                        "\n{}{key}: {value}{comma}",
+                        &source[line_start(source, next)..][..indent]
+                    )
+                }
+                false if comma.is_empty() => format!("{key}: {value}"),
+                false => format!("{key}: {value}, "),
+            };
+            // Inserted after the trivia on the same line to keep a comment following the brace
+            let at = match source[open + 1..next].contains('\n') {
+                true => open + 1,
+                false => next,
+            };
+            format!("{}{field}{}", &source[..at], &source[at..])
+        }
+    }
+}
+
+// Byte offset of the first character after whitespace and comments
+fn skip_trivia(source: &str, mut offset: usize) -> Option<usize> {
+    loop {
+        let rest = &source[offset..];
+        let trimmed = rest.trim_start();
+        offset += rest.len() - trimmed.len();
+        if trimmed.starts_with("//") {
+            offset += trimmed.find('\n')?;
+        } else if trimmed.starts_with("/*") {
+            offset += trimmed.find("*/")? + 2;
+        } else if trimmed.is_empty() {
+            return None;
+        } else {
+            return Some(offset);
+        }
+    }
+}
+
+fn line_start(source: &str, offset: usize) -> usize {
+    source[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start_matches([' ', '\t']).len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::console::Console;
+
+    fn set(source: &str, format: DefinitionFormat, assignments: &[&str]) -> String {
+        let fields = assignments
+            .iter()
+            .map(|a| parse_assignment(a).unwrap())
+            .collect::<Vec<_>>();
+        let edited = set_fields(source, format, &fields);
+        Header::from_definition(edited.as_bytes(), format).unwrap();
+        edited
+    }
+
+    #[test]
+    fn keeps_comments() {
+        let source =
+            "# mapper = 1\nmapper = 4 # TxROM\nprg_rom_size = \"128K\"\nchr_rom_size = \"8K\"\n";
+        assert_eq!(
+            set(
+                source,
+                DefinitionFormat::Toml,
+                &["mapper=1", "prg_rom_size=256K"]
+            ),
+            "# mapper = 1\nmapper = 1 # TxROM\nprg_rom_size = \"256K\"\nchr_rom_size = \"8K\"\n"
+        );
+        let source = "{\n  // why\n  mapper: 4, // TxROM\n  prg_rom_size: '128K',\n  chr_rom_size: '8K',\n}\n";
+        assert_eq!(
+            set(source, DefinitionFormat::Json5, &["mapper=MMC1"]),
+            "{\n  // why\n  mapper: \"MMC1\", // TxROM\n  prg_rom_size: '128K',\n  chr_rom_size: '8K',\n}\n"
+        );
+    }
+
+    #[test]
+    fn inserts_absent_fields() {
+        let source = "(\n    mapper: 4, // TxROM\n    prg_rom_size: \"128K\",\n    chr_rom_size: \"8K\",\n)\n";
+        assert_eq!(
+            set(source, DefinitionFormat::Ron, &["battery=true"]),
+            "(\n    battery: true,\n    mapper: 4, // TxROM\n    prg_rom_size: \"128K\",\n    chr_rom_size: \"8K\",\n)\n"
+        );
+        let source = r#"{"mapper": 4, "prg_rom_size": "128K", "chr_rom_size": "8K"}"#;
+        assert_eq!(
+            set(source, DefinitionFormat::Json, &["submapper=0x1"]),
+            r#"{"submapper": 1, "mapper": 4, "prg_rom_size": "128K", "chr_rom_size": "8K"}"#
+        );
+        let source = "mapper = 4\nprg_rom_size = \"128K\"\nchr_rom_size = \"8K\"\n\n[notes]\n";
+        assert_eq!(
+            set(source, DefinitionFormat::Toml, &["trainer=false"]),
+            "mapper = 4\nprg_rom_size = \"128K\"\nchr_rom_size = \"8K\"\ntrainer = false\n\n[notes]\n"
+        );
+    }
+
+    #[test]
+    fn replaces_yaml_block() {
+        let source =
+            "mapper: 4\nprg_rom_size:\n  exponent: 20\n  multiplier: 1\nchr_rom_size: 8K # chr\n";
+        assert_eq!(
+            set(source, DefinitionFormat::Yaml, &["prg_rom_size=128K"]),
+            "mapper: 4\nprg_rom_size: \"128K\"\nchr_rom_size: 8K # chr\n"
+        );
+    }
+
+    #[test]
+    fn invalid_assignment() {
+        assert!(parse_assignment("mapper").is_err());
+        assert!(parse_assignment("mapr=4").is_err());
+        assert!(parse_assignment("battery=yes").is_err());
+        assert_eq!(parse_assignment("mapper=MMC1").unwrap().1, "MMC1");
+    }
+
+    #[test]
+    fn sets_header_fields() {
+        let header = Header::builder().mapper(4).build().unwrap();
+        let fields = [parse_assignment("console=Vs System").unwrap()];
+        let header = set_header_fields(&header, &fields).unwrap();
+        assert_eq!(header.console, Some(Console::VsSystem));
+        assert_eq!(header.mapper, 4);
+    }
+}
@@ -0,0 +1,91 @@
+use anyhow::Result;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::io::{Read, Write};
+use std::path::Path;
+
+/// File format of definition file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DefinitionFormat {
+    Json,
+    /// JSON5, which also accepts JSON with comments
+    Json5,
+    Toml,
+    Yaml,
+    Ron,
+}
+
+impl DefinitionFormat {
+    /// Detect format from extension of the path
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .map(|e| e.to_ascii_lowercase());
+        match extension.as_deref() {
+            Some("json") => Some(DefinitionFormat::Json),
+            Some("json5" | "jsonc") => Some(DefinitionFormat::Json5),
+            Some("toml") => Some(DefinitionFormat::Toml),
+            Some("yaml" | "yml") => Some(DefinitionFormat::Yaml),
+            Some("ron") => Some(DefinitionFormat::Ron),
+            _ => None,
+        }
+    }
+
+    /// Deserialize value from reader written in this format
+    pub fn read<T: DeserializeOwned, R: Read>(self, mut r: R) -> Result<T> {
+        if self == DefinitionFormat::Json {
+            return Ok(serde_json::from_reader(r)?);
+        }
+        let mut source = String::new();
+        r.read_to_string(&mut source)?;
+        match self {
+            DefinitionFormat::Json => unreachable!(),
+            DefinitionFormat::Json5 => Ok(json5::from_str(&source)?),
+            DefinitionFormat::Toml => Ok(toml::from_str(&source)?),
+            DefinitionFormat::Yaml => Ok(serde_yaml::from_str(&source)?),
+            // Structs without name are read as maps to accept both `(..)` and `{..}`
+            DefinitionFormat::Ron => Ok(serde_json::from_value(ron::from_str::<Value>(&source)?)?),
+        }
+    }
+
+    /// Serialize value into writer in this format
+    pub fn write<W: Write>(self, mut w: W, value: &Value) -> Result<()> {
+        // Some formats can't express null, so fields without value are omitted
+        let value = match value {
+            Value::Object(fields) if self != DefinitionFormat::Json => Value::Object(
+                fields
+                    .iter()
+                    .filter(|(_, v)| !v.is_null())
+                    .map(|(k, v)| (k.clone(), v.clone()))
+                    .collect(),
+            ),
+            value => value.clone(),
+        };
+        match self {
+            DefinitionFormat::Json => serde_json::to_writer_pretty(&mut w, &value)?,
+            DefinitionFormat::Json5 => serde_json::to_writer_pretty(&mut w, &value)?,
+            DefinitionFormat::Toml => write!(w, "{}", toml::to_string_pretty(&value)?)?,
+            DefinitionFormat::Yaml => serde_yaml::to_writer(&mut w, &value)?,
+            DefinitionFormat::Ron => writeln!(w, "{}", to_ron(&value, 0)?)?,
+        }
+        Ok(())
+    }
+}
+
+// Write objects as structs without name, which is the usual way to write configuration in RON
+fn to_ron(value: &Value, depth: usize) -> Result<String> {
+    match value {
+        Value::Object(fields) => {
+            let indent = "    ".repeat(depth + 1);
+            let mut ron = String::from("(\n");
+            for (key, value) in fields {
+                ron.push_str(&format!("{indent}{key}: {},\n", to_ron(value, depth + 1)?));
+            }
+            ron.push_str(&"    ".repeat(depth));
+            ron.push(')');
+            Ok(ron)
+        }
+        value => Ok(ron::to_string(value)?),
+    }
+}
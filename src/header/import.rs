@@ -0,0 +1,427 @@
+use crate::codec;
+use anyhow::{Context, Result, anyhow, bail};
+use std::collections::HashMap;
+
+/// Evaluate header block of ca65, asm6 or NESASM source into 16 bytes
+pub fn parse_asm(source: &str) -> Result<[u8; 16]> {
+    let mut parser = AsmParser::default();
+    for (i, line) in source.lines().enumerate() {
+        // Code after the header may refer to labels which can't be evaluated
+        if parser
+            .header_start()
+            .is_some_and(|start| parser.bytes.len() >= start + 16)
+        {
+            break;
+        }
+        if let Err(e) = parser.parse_line(line) {
+            // NESASM writes the header from `.ines*` directives, so later data isn't header
+            let ines = parser.ines.iter().any(Option::is_some) && parser.header_start().is_none();
+            if !ines {
+                bail!("line {}: {e}: {}", i + 1, line.trim());
+            }
+        }
+    }
+    parser.finish()
+}
+
+#[derive(Default)]
+struct AsmParser {
+    constants: HashMap<String, i64>,
+    bytes: Vec<u8>,
+    in_other_segment: bool,
+    ines: [Option<i64>; 4],
+}
+
+impl AsmParser {
+    fn parse_line(&mut self, line: &str) -> Result<()> {
+        let line = strip_label(strip_comment(line).trim());
+        if line.is_empty() {
+            return Ok(());
+        }
+        let (word, rest) = split_word(line);
+        let directive = word.to_ascii_lowercase();
+
+        // Constant definitions
+        if directive == ".define" {
+            let (name, expr) = split_word(rest);
+            return self.define(name, expr);
+        }
+        let (second, expr) = split_word(rest);
+        if let Some(expr) = rest.strip_prefix(":=").or(rest.strip_prefix("=")) {
+            return self.define(word, expr);
+        } else if second.eq_ignore_ascii_case("equ") || second.eq_ignore_ascii_case(".equ") {
+            return self.define(word, expr);
+        }
+
+        match directive.as_str() {
+            ".segment" => {
+                self.in_other_segment = !rest.trim().trim_matches('"').eq("HEADER");
+            }
+            _ if self.in_other_segment => (),
+            ".byte" | ".byt" | ".db" | "db" | ".dc.b" => {
+                for arg in split_args(rest) {
+                    self.push_arg(arg.trim())?;
+                }
+            }
+            ".res" | ".ds" | "dsb" => {
+                let args = split_args(rest);
+                let count = self.eval(args.first().context("missing count")?)?;
+                let count = usize::try_from(count)
+                    .map_err(|_| anyhow!("count must not be negative: {count}"))?;
+                let fill = match args.get(1) {
+                    Some(fill) => to_byte(self.eval(fill)?)?,
+                    None => 0,
+                };
+                // Bytes beyond the header are never read, so only the missing ones are filled
+                let end = self.header_start().map_or(16, |start| start + 16);
+                let missing = end.saturating_sub(self.bytes.len());
+                let len = self.bytes.len() + count.min(missing);
+                self.bytes.resize(len, fill);
+            }
+            ".inesprg" => self.ines[0] = Some(self.eval(rest)?),
+            ".ineschr" => self.ines[1] = Some(self.eval(rest)?),
+            ".inesmap" => self.ines[2] = Some(self.eval(rest)?),
+            ".inesmir" => self.ines[3] = Some(self.eval(rest)?),
+            _ => (),
+        }
+        Ok(())
+    }
+
+    fn define(&mut self, name: &str, expr: &str) -> Result<()> {
+        let value = self.eval(expr)?;
+        self.constants.insert(name.to_string(), value);
+        Ok(())
+    }
+
+    fn eval(&self, expr: &str) -> Result<i64> {
+        let mut evaluator = Evaluator {
+            s: expr.trim().as_bytes(),
+            pos: 0,
+            constants: &self.constants,
+        };
+        let value = evaluator.expr(0)?;
+        evaluator.skip_spaces();
+        if evaluator.pos != evaluator.s.len() {
+            bail!("unexpected trailing characters in expression: {expr}");
+        }
+        Ok(value)
+    }
+
+    fn push_arg(&mut self, arg: &str) -> Result<()> {
+        if let Some(string) = arg.strip_prefix('"') {
+            let string = string
+                .strip_suffix('"')
+                .with_context(|| format!("unterminated string: {arg}"))?;
+            self.bytes.extend(string.bytes());
+        } else {
+            let value = self.eval(arg)?;
+            self.bytes.push(to_byte(value)?);
+        }
+        Ok(())
+    }
+
+    // Returns offset of "NES\x1a" in the bytes
+    fn header_start(&self) -> Option<usize> {
+        self.bytes.windows(4).position(|w| w == b"NES\x1a")
+    }
+
+    fn finish(self) -> Result<[u8; 16]> {
+        let start = self.header_start();
+        let mut header = [0; 16];
+        match start {
+            Some(start) => {
+                let bytes = self.bytes.get(start..start + 16).with_context(|| {
+                    format!("header is too short: {} bytes", self.bytes.len() - start)
+                })?;
+                header.copy_from_slice(bytes);
+            }
+            None => {
+                let [prg, chr, map, mir] = self.ines;
+                let prg = prg.context("no header block or .inesprg directive found")?;
+                let chr = chr.unwrap_or(0);
+                let map = map.unwrap_or(0);
+                let mir = mir.unwrap_or(0);
+                header[..4].copy_from_slice(b"NES\x1a");
+                header[4] = to_byte(prg)?;
+                header[5] = to_byte(chr)?;
+                header[6] = (to_byte(map)? & 0x0f) << 4 | (to_byte(mir)? & 0x0f);
+                header[7] = to_byte(map)? & 0xf0;
+            }
+        }
+        if header[7] & 0b1100 != 0b1000 {
+            codec::ines_to_nes2(&mut header);
+        }
+        Ok(header)
+    }
+}
+
+fn to_byte(value: i64) -> Result<u8> {
+    if !(-128..=255).contains(&value) {
+        bail!("value doesn't fit in a byte: {value}");
+    }
+    Ok(value as u8)
+}
+
+fn strip_comment(line: &str) -> &str {
+    let mut in_string = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ';' if !in_string => return &line[..i],
+            _ => (),
+        }
+    }
+    line
+}
+
+fn strip_label(line: &str) -> &str {
+    let end = line
+        .find(|c: char| !(c.is_ascii_alphanumeric() || matches!(c, '_' | '@' | '.')))
+        .unwrap_or(line.len());
+    match line[end..].strip_prefix(':') {
+        Some(rest) if end != 0 && !rest.starts_with('=') => rest.trim(),
+        _ => line,
+    }
+}
+
+fn split_word(line: &str) -> (&str, &str) {
+    let line = line.trim();
+    match line.find(|c: char| c.is_whitespace() || c == '=' || c == ':') {
+        Some(i) => (&line[..i], line[i..].trim()),
+        None => (line, ""),
+    }
+}
+
+// Split arguments by commas which are not in strings
+fn split_args(args: &str) -> Vec<&str> {
+    let mut result = Vec::new();
+    let mut in_string = false;
+    let mut start = 0;
+    for (i, c) in args.char_indices() {
+        match c {
+            '"' => in_string = !in_string,
+            ',' if !in_string => {
+                result.push(&args[start..i]);
+                start = i + 1;
+            }
+            _ => (),
+        }
+    }
+    if !args[start..].trim().is_empty() {
+        result.push(&args[start..]);
+    }
+    result
+}
+
+/// Evaluator of constant expression with C-like operator precedence
+struct Evaluator<'a> {
+    s: &'a [u8],
+    pos: usize,
+    constants: &'a HashMap<String, i64>,
+}
+
+/// Binary operators ordered from the lowest precedence
+const OPERATORS: &[&[&str]] = &[
+    &["|"],
+    &["^"],
+    &["&"],
+    &["<<", ">>"],
+    &["+", "-"],
+    &["*", "/", "%"],
+];
+
+impl Evaluator<'_> {
+    fn skip_spaces(&mut self) {
+        while self
+            .s
+            .get(self.pos)
+            .is_some_and(|c| c.is_ascii_whitespace())
+        {
+            self.pos += 1;
+        }
+    }
+
+    fn eat(&mut self, token: &str) -> bool {
+        self.skip_spaces();
+        if self.s[self.pos..].starts_with(token.as_bytes()) {
+            self.pos += token.len();
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expr(&mut self, level: usize) -> Result<i64> {
+        if level == OPERATORS.len() {
+            return self.unary();
+        }
+        let mut lhs = self.expr(level + 1)?;
+        'outer: loop {
+            for op in OPERATORS[level] {
+                if self.eat(op) {
+                    let rhs = self.expr(level + 1)?;
+                    lhs = match *op {
+                        "|" => lhs | rhs,
+                        "^" => lhs ^ rhs,
+                        "&" => lhs & rhs,
+                        "<<" => lhs.checked_shl(rhs as u32).context("too large shift")?,
+                        ">>" => lhs.checked_shr(rhs as u32).context("too large shift")?,
+                        "+" => lhs.wrapping_add(rhs),
+                        "-" => lhs.wrapping_sub(rhs),
+                        "*" => lhs.wrapping_mul(rhs),
+                        "/" | "%" if rhs == 0 => bail!("division by zero"),
+                        "/" => lhs.checked_div(rhs).context("overflow in division")?,
+                        "%" => lhs.checked_rem(rhs).context("overflow in division")?,
+                        _ => unreachable!(),
+                    };
+                    continue 'outer;
+                }
+            }
+            return Ok(lhs);
+        }
+    }
+
+    fn unary(&mut self) -> Result<i64> {
+        if self.eat("-") {
+            self.unary()?.checked_neg().context("overflow in negation")
+        } else if self.eat("~") {
+            Ok(!self.unary()?)
+        } else if self.eat("<") {
+            Ok(self.unary()? & 0xff)
+        } else if self.eat(">") {
+            Ok((self.unary()? >> 8) & 0xff)
+        } else {
+            self.primary()
+        }
+    }
+
+    fn primary(&mut self) -> Result<i64> {
+        self.skip_spaces();
+        if self.eat("(") {
+            let value = self.expr(0)?;
+            if !self.eat(")") {
+                bail!("missing closing parenthesis");
+            }
+            return Ok(value);
+        }
+        let rest = &self.s[self.pos..];
+        let (radix, prefix) = match rest {
+            [b'$', ..] => (16, 1),
+            [b'0', b'x' | b'X', ..] => (16, 2),
+            [b'%', ..] => (2, 1),
+            [b'0', b'b' | b'B', b'0' | b'1', ..] => (2, 2),
+            [b'\'', c, b'\'', ..] => {
+                self.pos += 3;
+                return Ok(*c as i64);
+            }
+            _ => (10, 0),
+        };
+        let is_digit = |c: &u8| (*c as char).is_digit(radix);
+        let len = rest[prefix..].iter().take_while(|c| is_digit(c)).count();
+        if len != 0 {
+            let digits = std::str::from_utf8(&rest[prefix..prefix + len])?;
+            self.pos += prefix + len;
+            return Ok(i64::from_str_radix(digits, radix)?);
+        }
+
+        let is_ident = |c: &u8| c.is_ascii_alphanumeric() || matches!(c, b'_' | b'@' | b'.');
+        let len = rest.iter().take_while(|c| is_ident(c)).count();
+        if len == 0 {
+            bail!("expected value: {}", String::from_utf8_lossy(rest));
+        }
+        let name = std::str::from_utf8(&rest[..len])?;
+        self.pos += len;
+        self.constants
+            .get(name)
+            .copied()
+            .ok_or_else(|| anyhow!("undefined constant: {name}"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(expr: &str) -> Result<i64> {
+        let mut parser = AsmParser::default();
+        parser.define("N", "4")?;
+        parser.eval(expr)
+    }
+
+    #[test]
+    fn precedence() {
+        let cases = [
+            ("1+2*3", 7),
+            ("(1+2)*3", 9),
+            ("1<<2+1", 8),
+            ("1|2^3&4", 3),
+            ("6&3^1", 3),
+            ("1|6&3", 3),
+            ("8>>1|1", 5),
+            ("7%4*2", 6),
+            ("10-4-3", 3),
+            ("16/4/2", 2),
+            ("2*N+1", 9),
+        ];
+        for (expr, value) in cases {
+            assert_eq!(eval(expr).unwrap(), value, "{expr}");
+        }
+    }
+
+    #[test]
+    fn unary_binds_tighter_than_binary() {
+        let cases = [
+            ("-2*3", -6),
+            ("-(2+3)", -5),
+            ("~0&$ff", 0xff),
+            ("<$1234", 0x34),
+            (">$1234", 0x12),
+            (">$1234+1", 0x13),
+            ("<$1234|>$1234", 0x36),
+            ("--1", 1),
+        ];
+        for (expr, value) in cases {
+            assert_eq!(eval(expr).unwrap(), value, "{expr}");
+        }
+    }
+
+    #[test]
+    fn number_literals() {
+        let cases = [
+            ("$1F", 0x1f),
+            ("0x1f", 0x1f),
+            ("%101", 5),
+            ("0b101", 5),
+            ("'A'", 65),
+            ("42", 42),
+            (" 1 + 2 ", 3),
+        ];
+        for (expr, value) in cases {
+            assert_eq!(eval(expr).unwrap(), value, "{expr}");
+        }
+    }
+
+    #[test]
+    fn invalid_expressions() {
+        for expr in ["1/0", "1%0", "(1", "1 2", "", "M", "1<<64"] {
+            assert!(eval(expr).is_err(), "{expr}");
+        }
+    }
+
+    #[test]
+    fn reserve_fills_only_the_header() {
+        let header = parse_asm(".byte \"NES\", $1a\n.res $7FFFFFFF, 8").unwrap();
+        assert_eq!(header[4..], [8; 12]);
+        assert!(parse_asm(".byte \"NES\", $1a\n.res -1").is_err());
+    }
+
+    #[test]
+    fn overflow_is_error() {
+        let message = |expr| eval(expr).unwrap_err().to_string();
+        assert_eq!(message("-(1<<63)"), "overflow in negation");
+        assert_eq!(message("(1<<63)/-1"), "overflow in division");
+        assert_eq!(message("(1<<63)%-1"), "overflow in division");
+        assert_eq!(message("1/0"), "division by zero");
+        assert!(parse_asm(".byte -(1<<63)").is_err());
+    }
+}
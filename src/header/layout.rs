@@ -0,0 +1,127 @@
+use super::Header;
+use super::console::Console;
+use anyhow::{Context, Result};
+use std::ops::{Range, RangeFrom};
+
+/// Size of trainer in bytes
+const TRAINER_SIZE: u64 = 512;
+
+/// Size of PlayChoice-10 INST-ROM in bytes
+const INST_ROM_SIZE: u64 = 8 * 1024;
+
+/// Size of PlayChoice-10 PROM in bytes, 16 bytes of data followed by 16 bytes of CounterOut
+const PROM_SIZE: u64 = 32;
+
+/// Byte ranges of the sections of NES file
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Layout {
+    pub header: Range<u64>,
+    pub trainer: Option<Range<u64>>,
+    pub prg_rom: Range<u64>,
+    pub chr_rom: Range<u64>,
+    /// PlayChoice-10 INST-ROM
+    pub inst_rom: Option<Range<u64>>,
+    /// PlayChoice-10 PROM
+    pub prom: Option<Range<u64>>,
+    /// Miscellaneous ROMs, which last until the end of file
+    pub misc_rom: RangeFrom<u64>,
+}
+
+impl Layout {
+    /// Returns layout of the file which has the header
+    pub fn of(header: &Header) -> Result<Self> {
+        let mut offset: u64 = 0;
+        let mut section = |size: u64, name: &str| -> Result<Range<u64>> {
+            let start = offset;
+            offset = offset
+                .checked_add(size)
+                .with_context(|| format!("{name} ends beyond 2^64 bytes"))?;
+            Ok(start..offset)
+        };
+        let playchoice10 = header.console == Some(Console::Playchoice10);
+
+        let header_range = section(16, "header")?;
+        let trainer = match header.trainer {
+            Some(true) => Some(section(TRAINER_SIZE, "trainer")?),
+            _ => None,
+        };
+        let prg_rom = section(header.prg_rom_size.bytes(), "PRG-ROM")?;
+        let chr_rom = section(header.chr_rom_size.bytes(), "CHR-ROM")?;
+        let inst_rom = match playchoice10 {
+            true => Some(section(INST_ROM_SIZE, "INST-ROM")?),
+            false => None,
+        };
+        let prom = match playchoice10 {
+            true => Some(section(PROM_SIZE, "PROM")?),
+            false => None,
+        };
+        Ok(Layout {
+            header: header_range,
+            trainer,
+            prg_rom,
+            chr_rom,
+            inst_rom,
+            prom,
+            misc_rom: offset..,
+        })
+    }
+
+    /// Returns names and ranges of the sections in file order, except misc ROMs
+    pub fn sections(&self) -> Vec<(&'static str, Range<u64>)> {
+        let sections = [
+            ("header", Some(self.header.clone())),
+            ("trainer", self.trainer.clone()),
+            ("prg_rom", Some(self.prg_rom.clone())),
+            ("chr_rom", Some(self.chr_rom.clone())),
+            ("inst_rom", self.inst_rom.clone()),
+            ("prom", self.prom.clone()),
+        ];
+        sections
+            .into_iter()
+            .filter_map(|(name, range)| Some((name, range?)))
+            .collect()
+    }
+
+    /// Returns range of misc ROMs in the file of the length, if it has any
+    pub fn misc_rom_in(&self, file_length: u64) -> Option<Range<u64>> {
+        (file_length > self.misc_rom.start).then_some(self.misc_rom.start..file_length)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::size::KiB;
+
+    #[test]
+    fn plain_layout() {
+        let header = Header::builder().prg_rom(KiB(32)).chr_rom(KiB(8));
+        let layout = header.build().unwrap().layout().unwrap();
+        assert_eq!(
+            layout.sections(),
+            [
+                ("header", 0..16),
+                ("prg_rom", 16..0x8010),
+                ("chr_rom", 0x8010..0xA010)
+            ]
+        );
+        assert_eq!(layout.misc_rom, 0xA010..);
+        assert_eq!(layout.misc_rom_in(0xA010), None);
+        assert_eq!(layout.misc_rom_in(0xA020), Some(0xA010..0xA020));
+    }
+
+    #[test]
+    fn trainer_and_playchoice10_sections() {
+        let header = Header::builder()
+            .trainer(true)
+            .console(Console::Playchoice10)
+            .prg_rom(KiB(16));
+        let layout = header.build().unwrap().layout().unwrap();
+        assert_eq!(layout.trainer, Some(16..0x210));
+        assert_eq!(layout.prg_rom, 0x210..0x4210);
+        assert_eq!(layout.chr_rom, 0x4210..0x4210);
+        assert_eq!(layout.inst_rom, Some(0x4210..0x6210));
+        assert_eq!(layout.prom, Some(0x6210..0x6230));
+        assert_eq!(layout.misc_rom, 0x6230..);
+    }
+}
@@ -0,0 +1,135 @@
+/// Mapper number, its names and the names of its submappers
+type MapperName = (u64, &'static [&'static str], &'static [(u64, &'static str)]);
+
+/// Names of commonly used mappers, the first name of each entry is the canonical one
+const MAPPERS: &[MapperName] = &[
+    (0, &["NROM"], &[]),
+    (1, &["MMC1", "SxROM"], &[(5, "SEROM")]),
+    (2, &["UxROM"], &[]),
+    (3, &["CNROM"], &[]),
+    (
+        4,
+        &["MMC3", "TxROM"],
+        &[(0, "MMC3C"), (1, "MMC6"), (3, "MC-ACC"), (4, "MMC3A")],
+    ),
+    (5, &["MMC5", "ExROM"], &[]),
+    (7, &["AxROM"], &[]),
+    (9, &["MMC2", "PxROM"], &[]),
+    (10, &["MMC4", "FxROM"], &[]),
+    (11, &["Color Dreams"], &[]),
+    (13, &["CPROM"], &[]),
+    (16, &["Bandai FCG"], &[(4, "FCG-1/2"), (5, "LZ93D50")]),
+    (18, &["Jaleco SS88006"], &[]),
+    (19, &["Namco 163"], &[]),
+    (21, &["VRC4ac"], &[(1, "VRC4a"), (2, "VRC4c")]),
+    (22, &["VRC2a"], &[]),
+    (
+        23,
+        &["VRC2b/VRC4ef"],
+        &[(1, "VRC4f"), (2, "VRC4e"), (3, "VRC2b")],
+    ),
+    (24, &["VRC6a"], &[]),
+    (
+        25,
+        &["VRC2c/VRC4bd"],
+        &[(1, "VRC4b"), (2, "VRC4d"), (3, "VRC2c")],
+    ),
+    (26, &["VRC6b"], &[]),
+    (28, &["Action 53"], &[]),
+    (30, &["UNROM 512"], &[]),
+    (32, &["Irem G-101"], &[(1, "Major League")]),
+    (33, &["Taito TC0190"], &[]),
+    (34, &["BNROM/NINA-001"], &[(1, "NINA-001"), (2, "BNROM")]),
+    (64, &["RAMBO-1"], &[]),
+    (65, &["Irem H3001"], &[]),
+    (66, &["GxROM"], &[]),
+    (67, &["Sunsoft-3"], &[]),
+    (68, &["Sunsoft-4"], &[]),
+    (69, &["Sunsoft FME-7"], &[]),
+    (70, &["Bandai 74161"], &[]),
+    (71, &["Camerica"], &[(1, "Fire Hawk")]),
+    (73, &["VRC3"], &[]),
+    (75, &["VRC1"], &[]),
+    (76, &["Namco 3446"], &[]),
+    (
+        78,
+        &["Jaleco JF-16"],
+        &[(1, "Cosmo Carrier"), (3, "Holy Diver")],
+    ),
+    (79, &["NINA-03/06"], &[]),
+    (85, &["VRC7"], &[]),
+    (86, &["Jaleco JF-13"], &[]),
+    (87, &["Jaleco J87"], &[]),
+    (94, &["UN1ROM"], &[]),
+    (97, &["Irem TAM-S1"], &[]),
+    (105, &["NES-EVENT"], &[]),
+    (111, &["GTROM"], &[]),
+    (118, &["TxSROM"], &[]),
+    (119, &["TQROM"], &[]),
+    (140, &["Jaleco JF-11/14"], &[]),
+    (152, &["Bandai 74161A"], &[]),
+    (159, &["Bandai LZ93D50 24C01"], &[]),
+    (180, &["UNROM 74HC08"], &[]),
+    (184, &["Sunsoft-1"], &[]),
+    (185, &["CNROM Protected"], &[]),
+    (206, &["Namco 118", "DxROM"], &[]),
+    (
+        210,
+        &["Namco 175/340"],
+        &[(1, "Namco 175"), (2, "Namco 340")],
+    ),
+    (232, &["Camerica Quattro"], &[]),
+];
+
+/// Returns mapper number which has the given name
+pub fn mapper_from_name(name: &str) -> Option<u64> {
+    MAPPERS
+        .iter()
+        .find(|(_, names, _)| names.iter().any(|n| n.eq_ignore_ascii_case(name)))
+        .map(|(number, _, _)| *number)
+}
+
+/// Returns canonical name of the mapper
+pub fn mapper_name(mapper: u64) -> Option<&'static str> {
+    MAPPERS
+        .iter()
+        .find(|(number, _, _)| *number == mapper)
+        .map(|(_, names, _)| names[0])
+}
+
+/// Returns submapper number of the mapper which has the given name
+pub fn submapper_from_name(mapper: u64, name: &str) -> Option<u64> {
+    MAPPERS
+        .iter()
+        .find(|(number, _, _)| *number == mapper)?
+        .2
+        .iter()
+        .find(|(_, n)| n.eq_ignore_ascii_case(name))
+        .map(|(number, _)| *number)
+}
+
+/// Returns name of the submapper of the mapper
+pub fn submapper_name(mapper: u64, submapper: u64) -> Option<&'static str> {
+    MAPPERS
+        .iter()
+        .find(|(number, _, _)| *number == mapper)?
+        .2
+        .iter()
+        .find(|(number, _)| *number == submapper)
+        .map(|(_, n)| *n)
+}
+
+/// Returns every known mapper name including aliases
+pub fn mapper_names() -> impl Iterator<Item = &'static str> {
+    MAPPERS
+        .iter()
+        .flat_map(|(_, names, _)| names.iter().copied())
+}
+
+/// Returns names of the submappers of the mapper
+pub fn submapper_names(mapper: u64) -> impl Iterator<Item = &'static str> {
+    MAPPERS
+        .iter()
+        .filter(move |(number, _, _)| *number == mapper)
+        .flat_map(|(_, _, submappers)| submappers.iter().map(|(_, name)| *name))
+}
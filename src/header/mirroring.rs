@@ -1,7 +1,9 @@
-use anyhow::{Error, bail};
+use super::diagnostic::did_you_mean;
+use super::name::{from_name, names};
+use anyhow::{Error, anyhow};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(try_from = "String", into = "String")]
 pub enum Mirroring {
     Horizontal,
@@ -9,16 +11,32 @@ pub enum Mirroring {
     FourScreens,
 }
 
+impl Mirroring {
+    /// All variants in declaration order
+    pub const ALL: [Mirroring; 3] = [
+        Mirroring::Horizontal,
+        Mirroring::Vertical,
+        Mirroring::FourScreens,
+    ];
+
+    /// Alternative names accepted in addition to the canonical ones
+    pub const ALIASES: [(&str, Mirroring); 3] = [
+        ("H", Mirroring::Horizontal),
+        ("V", Mirroring::Vertical),
+        ("4", Mirroring::FourScreens),
+    ];
+}
+
 impl TryFrom<String> for Mirroring {
     type Error = Error;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        match value.as_str() {
-            "Horizontal" => Ok(Mirroring::Horizontal),
-            "Vertical" => Ok(Mirroring::Vertical),
-            "FourScreens" => Ok(Mirroring::FourScreens),
-            _ => bail!("invalid mirroring name: {value}"),
-        }
+        from_name(&value, &Mirroring::ALL, &Mirroring::ALIASES).ok_or_else(|| {
+            anyhow!(
+                "invalid mirroring name: {value}{}",
+                did_you_mean(&value, names(&Mirroring::ALL, &Mirroring::ALIASES))
+            )
+        })
     }
 }
 
@@ -32,3 +50,26 @@ impl From<Mirroring> for String {
         value.to_string()
     }
 }
+
+/// Discriminant written in the header, which is defined in `codec::variants`
+impl From<Mirroring> for u8 {
+    fn from(value: Mirroring) -> Self {
+        use crate::codec::variants::mirroring;
+        match value {
+            Mirroring::Horizontal => mirroring::Horizontal,
+            Mirroring::Vertical => mirroring::Vertical,
+            Mirroring::FourScreens => mirroring::FourScreens,
+        }
+    }
+}
+
+impl TryFrom<u8> for Mirroring {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Mirroring::ALL
+            .into_iter()
+            .find(|variant| u8::from(*variant) == value)
+            .ok_or_else(|| anyhow!("invalid mirroring type: {value}"))
+    }
+}
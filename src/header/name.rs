@@ -0,0 +1,24 @@
+/// Returns the variant whose canonical name or alias matches the name case-insensitively
+pub fn from_name<T: Copy + Into<String>>(
+    name: &str,
+    all: &[T],
+    aliases: &[(&str, T)],
+) -> Option<T> {
+    all.iter()
+        .copied()
+        .find(|variant| (*variant).into().eq_ignore_ascii_case(name))
+        .or_else(|| {
+            aliases
+                .iter()
+                .find(|(alias, _)| alias.eq_ignore_ascii_case(name))
+                .map(|(_, variant)| *variant)
+        })
+}
+
+/// Returns canonical names followed by aliases
+pub fn names<T: Copy + Into<String>>(all: &[T], aliases: &[(&str, T)]) -> Vec<String> {
+    let canonical = all.iter().map(|variant| (*variant).into());
+    canonical
+        .chain(aliases.iter().map(|(alias, _)| alias.to_string()))
+        .collect()
+}
@@ -0,0 +1,242 @@
+use super::Header;
+use super::encoder::Encoder;
+use super::layout::Layout;
+use anyhow::{Context, Result, bail};
+use std::borrow::Cow;
+use std::io::{Read, Write};
+use std::ops::Range;
+
+/// Complete NES file, whose sections are borrowed from a slice or owned
+#[derive(Debug, Clone)]
+pub struct NesFile<'a> {
+    pub header: Header,
+    /// Header bytes as read, whose bits are kept unless the field in `header` is changed
+    pub original_header: Option<[u8; 16]>,
+    /// Byte 14 of the header, count of misc ROMs
+    pub misc_roms: u8,
+    /// Byte 15 of the header, default expansion device
+    pub expansion_device: u8,
+    pub trainer: Option<Cow<'a, [u8]>>,
+    pub prg_rom: Cow<'a, [u8]>,
+    pub chr_rom: Cow<'a, [u8]>,
+    /// PlayChoice-10 INST-ROM
+    pub inst_rom: Option<Cow<'a, [u8]>>,
+    /// PlayChoice-10 PROM
+    pub prom: Option<Cow<'a, [u8]>>,
+    /// Misc ROM area, which is empty unless the header counts misc ROMs
+    pub misc_rom: Cow<'a, [u8]>,
+    /// Data after the sections which the header doesn't describe
+    pub trailing: Cow<'a, [u8]>,
+}
+
+impl<'a> NesFile<'a> {
+    /// Parse file whose sections borrow the bytes
+    pub fn from_slice(bytes: &'a [u8]) -> Result<Self> {
+        let header = Encoder::new().encode(bytes)?;
+        let layout = Layout::of(&header)?;
+        let section = |range: Range<u64>, name: &str| -> Result<Cow<'a, [u8]>> {
+            let start = usize::try_from(range.start)?;
+            let end = usize::try_from(range.end)
+                .with_context(|| format!("{name} doesn't fit in memory"))?;
+            match bytes.get(start..end) {
+                Some(section) => Ok(Cow::Borrowed(section)),
+                None => bail!(
+                    "{name} is truncated: {} of {} bytes",
+                    bytes.len().saturating_sub(start),
+                    end - start
+                ),
+            }
+        };
+
+        let trainer = layout.trainer.map(|r| section(r, "trainer")).transpose()?;
+        let prg_rom = section(layout.prg_rom, "PRG-ROM")?;
+        let chr_rom = section(layout.chr_rom, "CHR-ROM")?;
+        let inst_rom = layout
+            .inst_rom
+            .map(|r| section(r, "INST-ROM"))
+            .transpose()?;
+        let prom = layout.prom.map(|r| section(r, "PROM")).transpose()?;
+
+        // The sections above are checked to be within the bytes
+        let rest = Cow::Borrowed(&bytes[layout.misc_rom.start as usize..]);
+        let misc_roms = bytes[14];
+        let (misc_rom, trailing) = match misc_roms & 0b11 {
+            0 => (Cow::Borrowed(&[][..]), rest),
+            _ => (rest, Cow::Borrowed(&[][..])),
+        };
+        Ok(NesFile {
+            header,
+            original_header: bytes[..16].try_into().ok(),
+            misc_roms,
+            expansion_device: bytes[15],
+            trainer,
+            prg_rom,
+            chr_rom,
+            inst_rom,
+            prom,
+            misc_rom,
+            trailing,
+        })
+    }
+
+    /// Returns file which owns its sections
+    pub fn into_owned(self) -> NesFile<'static> {
+        let owned = |section: Cow<'a, [u8]>| Cow::Owned(section.into_owned());
+        NesFile {
+            header: self.header,
+            original_header: self.original_header,
+            misc_roms: self.misc_roms,
+            expansion_device: self.expansion_device,
+            trainer: self.trainer.map(owned),
+            prg_rom: owned(self.prg_rom),
+            chr_rom: owned(self.chr_rom),
+            inst_rom: self.inst_rom.map(owned),
+            prom: self.prom.map(owned),
+            misc_rom: owned(self.misc_rom),
+            trailing: owned(self.trailing),
+        }
+    }
+
+    /// Write the header and sections in file order
+    pub fn write<W: Write>(&self, mut w: W) -> Result<()> {
+        self.validate()?;
+        let mut header = match self.original_header {
+            Some(original) => self.header.patch(&original)?,
+            None => self.header.encode()?,
+        };
+        header[14] = self.misc_roms;
+        header[15] = self.expansion_device;
+        w.write_all(&header)?;
+        let sections = [
+            self.trainer.as_deref(),
+            Some(&self.prg_rom[..]),
+            Some(&self.chr_rom[..]),
+            self.inst_rom.as_deref(),
+            self.prom.as_deref(),
+            Some(&self.misc_rom[..]),
+            Some(&self.trailing[..]),
+        ];
+        for section in sections.into_iter().flatten() {
+            w.write_all(section)?;
+        }
+        Ok(())
+    }
+
+    /// Returns bytes of the whole file
+    pub fn to_bytes(&self) -> Result<Vec<u8>> {
+        let mut bytes = Vec::new();
+        self.write(&mut bytes)?;
+        Ok(bytes)
+    }
+
+    // Check that lengths of the sections agree with the header
+    fn validate(&self) -> Result<()> {
+        let layout = Layout::of(&self.header)?;
+        let size = |range: &Range<u64>| range.end - range.start;
+        let sections = [
+            (
+                "trainer",
+                self.trainer.as_deref(),
+                layout.trainer.as_ref().map(size),
+            ),
+            (
+                "PRG-ROM",
+                Some(&self.prg_rom[..]),
+                Some(size(&layout.prg_rom)),
+            ),
+            (
+                "CHR-ROM",
+                Some(&self.chr_rom[..]),
+                Some(size(&layout.chr_rom)),
+            ),
+            (
+                "INST-ROM",
+                self.inst_rom.as_deref(),
+                layout.inst_rom.as_ref().map(size),
+            ),
+            ("PROM", self.prom.as_deref(), layout.prom.as_ref().map(size)),
+        ];
+        for (name, section, expected) in sections {
+            let actual = section.map(|s| s.len() as u64);
+            if actual != expected {
+                bail!(
+                    "{name} is {} bytes but the header says {}",
+                    actual.unwrap_or(0),
+                    expected.unwrap_or(0)
+                );
+            }
+        }
+        if self.misc_roms & 0b11 == 0 && !self.misc_rom.is_empty() {
+            bail!(
+                "misc ROM is {} bytes but the header counts none",
+                self.misc_rom.len()
+            );
+        }
+        Ok(())
+    }
+}
+
+impl NesFile<'static> {
+    /// Read the whole file from reader
+    pub fn read<R: Read>(mut r: R) -> Result<Self> {
+        let mut bytes = Vec::new();
+        r.read_to_end(&mut bytes)?;
+        Ok(NesFile::from_slice(&bytes)?.into_owned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::header::mirroring::Mirroring;
+
+    // Four-screen with vertical bit, which `Header` can't hold, and trailing data
+    fn file() -> Vec<u8> {
+        let mut bytes = b"NES\x1a\x01\x01\x09\x08\0\0\0\0\0\0\0\0".to_vec();
+        bytes.extend((0..16 * 1024 + 8 * 1024).map(|i| i as u8));
+        bytes.extend(b"tail");
+        bytes
+    }
+
+    #[test]
+    fn round_trip_keeps_bytes() {
+        let bytes = file();
+        let file = NesFile::from_slice(&bytes).unwrap();
+        assert_eq!(file.header.mirroring, Some(Mirroring::FourScreens));
+        assert_eq!(&file.trailing[..], b"tail");
+        assert_eq!(file.to_bytes().unwrap(), bytes);
+    }
+
+    #[test]
+    fn edit_keeps_original_header_bits() {
+        let bytes = file();
+        let mut file = NesFile::from_slice(&bytes).unwrap();
+        file.header.mapper = 0x12;
+        let edited = file.to_bytes().unwrap();
+        assert_eq!(edited[6], 0x29);
+        assert_eq!(edited[7], 0x18);
+        assert_eq!(edited[16..], bytes[16..]);
+
+        // Mirroring bits are rewritten only when mirroring is changed
+        file.header.mirroring = Some(Mirroring::Horizontal);
+        assert_eq!(file.to_bytes().unwrap()[6], 0x20);
+    }
+
+    #[test]
+    fn sections_must_agree_with_header() {
+        let bytes = file();
+        let mut file = NesFile::from_slice(&bytes).unwrap();
+        file.chr_rom = Cow::Borrowed(&bytes[16..32]);
+        let error = file.to_bytes().unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "CHR-ROM is 16 bytes but the header says 8192"
+        );
+
+        let error = NesFile::from_slice(&bytes[..1000]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "PRG-ROM is truncated: 984 of 16384 bytes"
+        );
+    }
+}
@@ -0,0 +1,182 @@
+use super::Header;
+use super::mirroring::Mirroring;
+
+/// Deprecated mapper assignment and its canonical replacement
+#[derive(Debug, Clone, Copy)]
+pub struct Remap {
+    /// Mapper and submapper of the deprecated assignment
+    pub legacy: (u64, u64),
+    /// Mapper and submapper of the canonical assignment
+    pub modern: (u64, u64),
+    pub description: &'static str,
+    condition: fn(&Header) -> bool,
+    /// Mirroring which the deprecated assignment is told by, restored by `legacy`
+    legacy_mirroring: Option<Mirroring>,
+}
+
+impl Remap {
+    /// Returns true if the canonical assignment is another submapper of the same mapper
+    pub fn is_submapper_fold(&self) -> bool {
+        self.legacy.0 == self.modern.0
+    }
+}
+
+/// Applied change of mapper and submapper
+#[derive(Debug, Clone, Copy)]
+pub struct Remapping {
+    pub from: (u64, u64),
+    pub to: (u64, u64),
+    pub description: &'static str,
+}
+
+const fn always(_: &Header) -> bool {
+    true
+}
+
+fn has_chr_rom(header: &Header) -> bool {
+    header.chr_rom_size.bytes() != 0
+}
+
+fn is_four_screen(header: &Header) -> bool {
+    header.mirroring == Some(Mirroring::FourScreens)
+}
+
+fn has_24c02(header: &Header) -> bool {
+    header
+        .prg_nvram_size
+        .is_some_and(|size| size.bytes() == 256)
+}
+
+/// Known deprecated assignments
+pub const REMAPS: &[Remap] = &[
+    Remap {
+        legacy: (16, 0),
+        modern: (16, 5),
+        description: "Bandai LZ93D50 with 24C02",
+        condition: has_24c02,
+        legacy_mirroring: None,
+    },
+    Remap {
+        legacy: (34, 0),
+        modern: (34, 1),
+        description: "NINA-001 has CHR-ROM",
+        condition: has_chr_rom,
+        legacy_mirroring: None,
+    },
+    Remap {
+        legacy: (34, 0),
+        modern: (34, 2),
+        description: "BNROM has CHR-RAM",
+        condition: |header| !has_chr_rom(header),
+        legacy_mirroring: None,
+    },
+    // iNES marked Holy Diver by four-screen bit, which the board doesn't have
+    Remap {
+        legacy: (78, 0),
+        modern: (78, 3),
+        description: "Holy Diver has mapper-controlled mirroring",
+        condition: is_four_screen,
+        legacy_mirroring: Some(Mirroring::FourScreens),
+    },
+    Remap {
+        legacy: (78, 0),
+        modern: (78, 1),
+        description: "Cosmo Carrier has single-screen mirroring",
+        condition: |header| !is_four_screen(header),
+        legacy_mirroring: None,
+    },
+    Remap {
+        legacy: (39, 0),
+        modern: (241, 0),
+        description: "duplicate of mapper 241",
+        condition: always,
+        legacy_mirroring: None,
+    },
+    Remap {
+        legacy: (122, 0),
+        modern: (184, 0),
+        description: "duplicate of Sunsoft-1",
+        condition: always,
+        legacy_mirroring: None,
+    },
+    Remap {
+        legacy: (151, 0),
+        modern: (75, 0),
+        description: "duplicate of VRC1",
+        condition: always,
+        legacy_mirroring: None,
+    },
+    Remap {
+        legacy: (161, 0),
+        modern: (1, 0),
+        description: "duplicate of MMC1",
+        condition: always,
+        legacy_mirroring: None,
+    },
+];
+
+/// Rewrite deprecated mapper and submapper into canonical assignment
+pub fn modernize(header: &mut Header) -> Option<Remapping> {
+    let from = (header.mapper, header.submapper.unwrap_or(0));
+    let remap = REMAPS
+        .iter()
+        .find(|r| r.legacy == from && (r.condition)(header))?;
+    Some(apply(header, remap.modern, remap.description))
+}
+
+/// Rewrite canonical submapper into deprecated one for emulators which don't know submappers.
+///
+/// Duplicated mapper numbers are kept since the canonical one is known by such emulators.
+/// The mirroring the deprecated assignment is told by is restored, and the header is kept as is
+/// if it doesn't meet the condition to modernize back into the same submapper.
+pub fn legacy(header: &mut Header) -> Option<Remapping> {
+    let from = (header.mapper, header.submapper.unwrap_or(0));
+    let remap = REMAPS.iter().find(|r| {
+        let mut legacy = header.clone();
+        legacy.mirroring = r.legacy_mirroring.or(legacy.mirroring);
+        r.is_submapper_fold() && r.modern == from && (r.condition)(&legacy)
+    })?;
+    header.mirroring = remap.legacy_mirroring.or(header.mirroring);
+    Some(apply(header, remap.legacy, remap.description))
+}
+
+fn apply(header: &mut Header, to: (u64, u64), description: &'static str) -> Remapping {
+    let from = (header.mapper, header.submapper.unwrap_or(0));
+    header.mapper = to.0;
+    header.submapper = Some(to.1);
+    Remapping {
+        from,
+        to,
+        description,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(mapper: u64, submapper: u64, mirroring: Mirroring) -> Header {
+        let header = Header::builder().mapper(mapper).submapper(submapper);
+        header.mirroring(mirroring).build().unwrap()
+    }
+
+    #[test]
+    fn legacy_restores_four_screens() {
+        let mut holy_diver = header(78, 3, Mirroring::Horizontal);
+        let remapping = holy_diver.legacy().unwrap();
+        assert_eq!(remapping.to, (78, 0));
+        assert_eq!(holy_diver.mirroring, Some(Mirroring::FourScreens));
+        assert_eq!(holy_diver.modernize().unwrap().to, (78, 3));
+    }
+
+    #[test]
+    fn legacy_keeps_header_which_would_not_come_back() {
+        let mut cosmo_carrier = header(78, 1, Mirroring::FourScreens);
+        assert!(cosmo_carrier.legacy().is_none());
+        assert_eq!(cosmo_carrier.submapper, Some(1));
+
+        let mut cosmo_carrier = header(78, 1, Mirroring::Vertical);
+        assert_eq!(cosmo_carrier.legacy().unwrap().to, (78, 0));
+        assert_eq!(cosmo_carrier.modernize().unwrap().to, (78, 1));
+    }
+}
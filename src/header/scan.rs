@@ -0,0 +1,75 @@
+use super::Header;
+use super::encoder::Encoder;
+use crate::codec;
+use anyhow::{Context, Result, bail};
+use serde::Serialize;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// Result of reading the header of a file in collection
+#[derive(Serialize, Debug, Clone)]
+pub struct Record {
+    pub path: PathBuf,
+    /// Size of the whole file in bytes
+    pub size: Option<u64>,
+    /// Format of the header, which is "iNES", "NES 2.0" or "archaic"
+    pub format: Option<&'static str>,
+    /// Header, where fields which iNES doesn't have are null
+    pub header: Option<Header>,
+    /// Caveat of the decoded header
+    pub note: Option<&'static str>,
+    pub error: Option<String>,
+}
+
+impl Record {
+    /// Read only the header of the file, recording the error rather than failing
+    pub fn of(path: &Path) -> Self {
+        let mut record = Record {
+            path: path.to_path_buf(),
+            size: None,
+            format: None,
+            header: None,
+            note: None,
+            error: None,
+        };
+        if let Err(e) = record.read() {
+            record.error = Some(e.to_string());
+        }
+        record
+    }
+
+    /// Record of the file which couldn't be reached, like unreadable directory
+    pub fn failed(path: &Path, error: impl ToString) -> Self {
+        Record {
+            path: path.to_path_buf(),
+            size: None,
+            format: None,
+            header: None,
+            note: None,
+            error: Some(error.to_string()),
+        }
+    }
+
+    fn read(&mut self) -> Result<()> {
+        let file = File::open(&self.path).context("failed to open file")?;
+        self.size = Some(file.metadata()?.len());
+        let mut bytes = Vec::with_capacity(16);
+        file.take(16).read_to_end(&mut bytes)?;
+        let Ok(bytes) = <[u8; 16]>::try_from(bytes.as_slice()) else {
+            bail!("file is shorter than header: {} bytes", bytes.len());
+        };
+        let format = codec::format(&bytes)?;
+        self.format = Some(format.name());
+        match format {
+            codec::Format::Nes2 => self.header = Some(Encoder::new().encode(bytes.as_slice())?),
+            codec::Format::Ines => self.header = Some(Header::from_ines(&bytes)?),
+            codec::Format::Archaic => {
+                self.header = Some(Header::from_ines(&bytes)?);
+                self.note =
+                    Some("archaic iNES header, so only the lower 4 bits of mapper are read");
+            }
+        }
+        Ok(())
+    }
+}
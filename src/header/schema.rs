@@ -0,0 +1,141 @@
+use super::board::BOARDS;
+use super::console::Console;
+use super::mapper::mapper_names;
+use super::mirroring::Mirroring;
+use super::name;
+use super::timing::Timing;
+use super::vs_system::{hardware::VsSystemHardware, ppu::VsSystemPPU};
+use serde_json::{Value, json};
+
+/// Factor of size literal, see `parse_size_as_byte`
+const FACTOR: &str = r"\s*(0[xX][0-9A-Fa-f]+|[0-9]+)\s*([kKmM]([iI]?[bB])?)?\s*";
+
+/// Factor of ROM size literal, which also accepts bank count
+const ROM_FACTOR: &str =
+    r"\s*(0[xX][0-9A-Fa-f]+|[0-9]+)\s*([kKmM]([iI]?[bB])?|[bB][aA][nN][kK][sS]?)?\s*";
+
+/// Returns JSON Schema of header definition file
+pub fn definition_schema() -> Value {
+    // Canonical spellings are listed for completion, while the pattern accepts any case
+    let names = |names: Vec<String>| {
+        json!({
+            "anyOf": [
+                { "enum": names },
+                { "type": "string", "pattern": case_insensitive(&names) }
+            ]
+        })
+    };
+    let rom_size = json!({
+        "oneOf": [
+            { "type": "string", "pattern": format!("^{ROM_FACTOR}([xX*]{ROM_FACTOR})*$") },
+            {
+                "type": "object",
+                "properties": {
+                    "exponent": { "type": "integer", "minimum": 0, "maximum": 60 },
+                    "multiplier": { "type": "integer", "minimum": 0, "maximum": 3 }
+                },
+                "required": ["exponent", "multiplier"],
+                "additionalProperties": false
+            }
+        ]
+    });
+    let ram_size = json!({
+        "type": "string",
+        "pattern": format!("^{FACTOR}([xX*]{FACTOR})*$")
+    });
+    // Vs System fields are written as null in dump of the other consoles
+    let nullable = |names: Vec<String>| {
+        json!({
+            "anyOf": [
+                { "enum": names },
+                { "type": "string", "pattern": case_insensitive(&names) },
+                { "type": "null" }
+            ]
+        })
+    };
+
+    let aliases = Console::ALIASES
+        .iter()
+        .filter(|(_, c)| *c == Console::VsSystem);
+    let vs_system = name::names(&[Console::VsSystem], &aliases.copied().collect::<Vec<_>>());
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": "NES 2.0 header definition",
+        "type": "object",
+        "properties": {
+            "board": names(BOARDS.iter().map(|b| b.name.to_string()).collect()),
+            "mapper": {
+                "oneOf": [
+                    { "type": "integer", "minimum": 0, "maximum": 0xFFF },
+                    names(mapper_names().map(String::from).collect())
+                ]
+            },
+            "submapper": {
+                "oneOf": [
+                    { "type": "integer", "minimum": 0, "maximum": 0xF },
+                    { "type": "string" }
+                ]
+            },
+            "mirroring": names(name::names(&Mirroring::ALL, &Mirroring::ALIASES)),
+            "battery": { "type": "boolean" },
+            "trainer": { "type": "boolean" },
+            "console": names(name::names(&Console::ALL, &Console::ALIASES)),
+            "timing": names(name::names(&Timing::ALL, &Timing::ALIASES)),
+            "vs_system_ppu": nullable(VsSystemPPU::ALL.map(String::from).to_vec()),
+            "vs_system_hardware": nullable(VsSystemHardware::ALL.map(String::from).to_vec()),
+            "prg_rom_size": rom_size,
+            "prg_ram_size": ram_size,
+            "prg_nvram_size": ram_size,
+            "chr_rom_size": rom_size,
+            "chr_ram_size": ram_size,
+            "chr_nvram_size": ram_size
+        },
+        "anyOf": [
+            { "required": ["board"] },
+            { "required": ["mapper", "prg_rom_size", "chr_rom_size"] }
+        ],
+        "if": {
+            "properties": {
+                "console": { "type": "string", "pattern": case_insensitive(&vs_system) }
+            },
+            "required": ["console"]
+        },
+        "else": {
+            "properties": {
+                "vs_system_ppu": { "type": "null" },
+                "vs_system_hardware": { "type": "null" }
+            }
+        }
+    })
+}
+
+// Returns regular expression which matches any of the names ignoring ASCII case
+fn case_insensitive(names: &[String]) -> String {
+    let alternatives = names.iter().map(|name| {
+        name.chars()
+            .map(|c| match c {
+                c if c.is_ascii_alphabetic() => {
+                    format!("[{}{}]", c.to_ascii_lowercase(), c.to_ascii_uppercase())
+                }
+                c if r"^$\.*+?()[]{}|".contains(c) => format!("\\{c}"),
+                c => c.to_string(),
+            })
+            .collect::<String>()
+    });
+    format!("^({})$", alternatives.collect::<Vec<_>>().join("|"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_only_syntax_characters() {
+        let names = ["NES-NROM-128".to_string(), "A/B (C.D)".to_string()];
+        assert_eq!(
+            case_insensitive(&names),
+            r"^([nN][eE][sS]-[nN][rR][oO][mM]-128|[aA]/[bB] \([cC]\.[dD]\))$"
+        );
+    }
+}
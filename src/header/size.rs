@@ -0,0 +1,551 @@
+use anyhow::{Context, Error, Result, anyhow, bail};
+use serde::{Deserialize, Serialize};
+use std::str::FromStr;
+
+/// Size of PRG-ROM, counted in 16K units
+pub type PrgRomSize = RomSize<{ 16 * 1024 }>;
+
+/// Size of CHR-ROM, counted in 8K units
+pub type ChrRomSize = RomSize<{ 8 * 1024 }>;
+
+/// Size of ROM area, either a count of `UNIT` bytes or exponent-multiplier notation,
+/// which is checked to be in range of the header when created
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(try_from = "RomSizeNotation", into = "RomSizeNotation")]
+pub struct RomSize<const UNIT: u64>(Notation);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Notation {
+    /// Count of `UNIT` bytes, which must be less than or equal to 0xEFF
+    Units(u16),
+    /// 2^exponent * (multiplier * 2 + 1) bytes
+    ExponentMultiplier { exponent: u8, multiplier: u8 },
+}
+
+impl<const UNIT: u64> RomSize<UNIT> {
+    /// Create a size from count of `UNIT` bytes
+    pub fn from_units(units: u64) -> Result<Self> {
+        if units > 0xEFF {
+            bail!("too many rom units: {units}");
+        }
+        Ok(RomSize(Notation::Units(units as u16)))
+    }
+
+    /// Create a size from exponent-multiplier notation
+    pub fn from_exponent_multiplier(exponent: u8, multiplier: u8) -> Result<Self> {
+        if exponent > 60 {
+            bail!("too large rom size exponent: {exponent}");
+        } else if multiplier > 3 {
+            bail!("too large rom size multiplier: {multiplier}");
+        }
+        Ok(RomSize(Notation::ExponentMultiplier {
+            exponent,
+            multiplier,
+        }))
+    }
+
+    /// Create a size from bytes, prefering count of units to exponent-multiplier notation
+    pub fn from_bytes(bytes: u64) -> Result<Self> {
+        if bytes.is_multiple_of(UNIT) && bytes / UNIT <= 0xEFF {
+            return Self::from_units(bytes / UNIT);
+        }
+        Self::exponent_multiplier_of(bytes)
+    }
+
+    // Returns exponent-multiplier notation of the bytes
+    fn exponent_multiplier_of(bytes: u64) -> Result<Self> {
+        for exponent in 0..=60 {
+            for multiplier in 0..=3 {
+                if bytes == (1 << exponent) * (multiplier as u64 * 2 + 1) {
+                    return Self::from_exponent_multiplier(exponent, multiplier);
+                }
+            }
+        }
+        bail!("invalid rom size: {bytes}");
+    }
+
+    /// Create a size from notation of the area which has the given name, like "PRG-ROM"
+    pub fn from_notation(notation: RomSizeNotation, name: &str) -> Result<Self> {
+        match notation {
+            RomSizeNotation::Literal(literal) => Self::parse_as(&literal, name),
+            RomSizeNotation::ExponentMultiplier {
+                exponent,
+                multiplier,
+            } => Self::from_exponent_multiplier(exponent, multiplier)
+                .map_err(|e| anyhow!("invalid {name} size: {e}")),
+        }
+    }
+
+    /// Returns the same size written in count of `UNIT` bytes
+    pub fn to_units(self) -> Result<Self> {
+        let bytes = self.bytes();
+        if !bytes.is_multiple_of(UNIT) {
+            bail!("{bytes} bytes is not a multiple of {}K", UNIT / 1024);
+        }
+        Self::from_units(bytes / UNIT)
+    }
+
+    /// Returns the same size written in exponent-multiplier notation
+    pub fn to_exponent_multiplier(self) -> Result<Self> {
+        Self::exponent_multiplier_of(self.bytes())
+    }
+
+    /// Parse size literal of the area which has the given name, like "PRG-ROM"
+    pub fn parse_as(literal: &str, name: &str) -> Result<Self> {
+        let bytes = parse_size_as_byte(literal, Some(UNIT))
+            .map_err(|e| anyhow!("invalid {name} size literal {literal}: {e}"))?;
+        match Self::from_bytes(bytes) {
+            Ok(size) => Ok(size),
+            Err(_) => {
+                let candidates = Self::candidates(bytes).into_iter();
+                let candidates = candidates.map(String::from).collect::<Vec<_>>();
+                bail!(
+                    "{literal} {name} is neither a multiple of {}K nor exponent-multiplier; nearest: {}",
+                    UNIT / 1024,
+                    candidates.join(", ")
+                )
+            }
+        }
+    }
+
+    /// Returns the nearest representable sizes below and above the bytes
+    fn candidates(bytes: u64) -> Vec<Self> {
+        let units = (bytes / UNIT..=bytes.div_ceil(UNIT))
+            .filter(|units| *units <= 0xEFF)
+            .map(|units| RomSize(Notation::Units(units as u16)));
+        let exponents = (0..=60).flat_map(|exponent| {
+            (0..=3).map(move |multiplier| {
+                RomSize(Notation::ExponentMultiplier {
+                    exponent,
+                    multiplier,
+                })
+            })
+        });
+        nearest(units.chain(exponents), bytes, Self::bytes)
+    }
+
+    /// Returns size in bytes
+    pub fn bytes(&self) -> u64 {
+        match self.0 {
+            Notation::Units(units) => units as u64 * UNIT,
+            Notation::ExponentMultiplier {
+                exponent,
+                multiplier,
+            } => (1 << exponent) * (multiplier as u64 * 2 + 1),
+        }
+    }
+
+    /// Returns count of `UNIT` bytes if the size is written in units
+    pub fn units(&self) -> Option<u64> {
+        match self.0 {
+            Notation::Units(units) => Some(units as u64),
+            Notation::ExponentMultiplier { .. } => None,
+        }
+    }
+
+    /// Returns exponent and multiplier if the size is written in exponent-multiplier notation
+    pub fn exponent_multiplier(&self) -> Option<(u8, u8)> {
+        match self.0 {
+            Notation::Units(_) => None,
+            Notation::ExponentMultiplier {
+                exponent,
+                multiplier,
+            } => Some((exponent, multiplier)),
+        }
+    }
+
+    /// Create a size from 12-bit value of the header
+    pub fn from_raw(raw: u16) -> Result<Self> {
+        let lsb = raw as u8;
+        match raw >> 8 {
+            0xF => Self::from_exponent_multiplier(lsb >> 2, lsb & 0b11),
+            0x0..=0xE => Self::from_units(raw as u64),
+            _ => bail!("too large raw rom size: {raw:#x}"),
+        }
+    }
+
+    /// Returns 12-bit value of the header
+    pub fn raw(&self) -> u16 {
+        match self.0 {
+            Notation::Units(units) => units,
+            Notation::ExponentMultiplier {
+                exponent,
+                multiplier,
+            } => 0xF00 | ((exponent as u16) << 2) | multiplier as u16,
+        }
+    }
+}
+
+impl<const UNIT: u64> Default for RomSize<UNIT> {
+    fn default() -> Self {
+        RomSize(Notation::Units(0))
+    }
+}
+
+impl<const UNIT: u64> FromStr for RomSize<UNIT> {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_as(s, "ROM")
+    }
+}
+
+impl<const UNIT: u64> TryFrom<RomSizeNotation> for RomSize<UNIT> {
+    type Error = Error;
+
+    fn try_from(value: RomSizeNotation) -> Result<Self, Self::Error> {
+        Self::from_notation(value, "ROM")
+    }
+}
+
+impl<const UNIT: u64> From<RomSize<UNIT>> for RomSizeNotation {
+    fn from(value: RomSize<UNIT>) -> Self {
+        match value.0 {
+            Notation::Units(_) => RomSizeNotation::Literal(value.into()),
+            Notation::ExponentMultiplier {
+                exponent,
+                multiplier,
+            } => RomSizeNotation::ExponentMultiplier {
+                exponent,
+                multiplier,
+            },
+        }
+    }
+}
+
+impl<const UNIT: u64> From<RomSize<UNIT>> for String {
+    fn from(value: RomSize<UNIT>) -> Self {
+        match value.0 {
+            Notation::Units(_) => {
+                let size = value.bytes() / 1024;
+                if size != 0 && size.is_multiple_of(1024) {
+                    format!("{}M", size / 1024)
+                } else {
+                    format!("{}K", size)
+                }
+            }
+            Notation::ExponentMultiplier { .. } => format!("{}", value.bytes()),
+        }
+    }
+}
+
+/// Size in kibibytes, convertible into bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KiB(pub u64);
+
+impl From<KiB> for u64 {
+    fn from(value: KiB) -> Self {
+        value.0 * 1024
+    }
+}
+
+/// Size in mebibytes, convertible into bytes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MiB(pub u64);
+
+impl From<MiB> for u64 {
+    fn from(value: MiB) -> Self {
+        value.0 * 1024 * 1024
+    }
+}
+
+/// Size of ROM area as written in definition file
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(untagged)]
+pub enum RomSizeNotation {
+    /// Size literal like "128K"
+    Literal(String),
+    /// Explicit exponent-multiplier notation
+    ExponentMultiplier { exponent: u8, multiplier: u8 },
+}
+
+/// How ROM sizes are encoded into header
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RomSizeEncoding {
+    /// Count of units if possible, exponent-multiplier otherwise
+    Auto,
+    /// Always count of units
+    Units,
+    /// Always exponent-multiplier
+    ExponentMultiplier,
+}
+
+/// Size of RAM area, which is zero or 64 bytes shifted left by the count
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(try_from = "String", into = "String")]
+pub struct RamSize(u8);
+
+impl RamSize {
+    /// Create a size from shift count
+    pub fn from_shift(count: u8) -> Result<Self> {
+        if count > 0xF {
+            bail!("too large ram shift count: {count}");
+        }
+        Ok(RamSize(count))
+    }
+
+    /// Create a size from bytes
+    pub fn from_bytes(bytes: u64) -> Result<Self> {
+        for count in 0x00..=0xF {
+            if (count == 0 && bytes == 0) || (count != 0 && 64 << count == bytes) {
+                return Ok(RamSize(count));
+            }
+        }
+        bail!("invalid ram size: {bytes}");
+    }
+
+    /// Parse size literal of the area which has the given name, like "PRG-RAM"
+    pub fn parse_as(literal: &str, name: &str) -> Result<Self> {
+        let bytes = parse_size_as_byte(literal, None)
+            .map_err(|e| anyhow!("invalid {name} size literal {literal}: {e}"))?;
+        match Self::from_bytes(bytes) {
+            Ok(size) => Ok(size),
+            Err(_) => {
+                let sizes = (0..=0xF).map(RamSize);
+                let candidates = nearest(sizes, bytes, Self::bytes).into_iter();
+                let candidates = candidates.map(String::from).collect::<Vec<_>>();
+                bail!(
+                    "{literal} {name} is not a power-of-two shift; nearest: {}",
+                    candidates.join(", ")
+                )
+            }
+        }
+    }
+
+    /// Returns shift count
+    pub fn shift(&self) -> u8 {
+        self.0
+    }
+
+    /// Returns size in bytes
+    pub fn bytes(&self) -> u64 {
+        if self.0 == 0 { 0 } else { 64 << self.0 }
+    }
+}
+
+impl FromStr for RamSize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse_as(s, "RAM")
+    }
+}
+
+impl TryFrom<String> for RamSize {
+    type Error = Error;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<RamSize> for String {
+    fn from(value: RamSize) -> Self {
+        let size = value.bytes();
+        if size.is_multiple_of(1024) {
+            format!("{}K", size / 1024)
+        } else {
+            format!("{}", size)
+        }
+    }
+}
+
+/// Returns the largest size below the bytes and the smallest size above the bytes
+fn nearest<T: Copy>(sizes: impl Iterator<Item = T>, bytes: u64, f: fn(&T) -> u64) -> Vec<T> {
+    let mut below: Option<T> = None;
+    let mut above: Option<T> = None;
+    for size in sizes {
+        let n = f(&size);
+        if n < bytes && below.is_none_or(|below| f(&below) < n) {
+            below = Some(size);
+        } else if n > bytes && above.is_none_or(|above| f(&above) > n) {
+            above = Some(size);
+        }
+    }
+    below.into_iter().chain(above).collect()
+}
+
+/// Parse size literal as bytes.
+///
+/// The literal is a product of factors separated by `x` or `*`. Each factor is a decimal or
+/// hexadecimal number optionally followed by `K`, `KB`, `KiB`, `M`, `MB`, `MiB` or, if `bank` is
+/// given, `banks`. Suffixes are case insensitive and `KB` means 1024 bytes as `KiB` does.
+pub fn parse_size_as_byte(size: &str, bank: Option<u64>) -> Result<u64> {
+    let mut bytes: u64 = 1;
+    for factor in split_factors(size) {
+        let n = parse_factor(factor.trim(), bank)?;
+        bytes = bytes
+            .checked_mul(n)
+            .with_context(|| "too big size literal".to_string())?;
+    }
+    Ok(bytes)
+}
+
+// Split size literal by `x` and `*` but not by `x` of hexadecimal prefix
+fn split_factors(size: &str) -> Vec<&str> {
+    let mut factors = Vec::new();
+    let mut start = 0;
+    for (i, c) in size.char_indices() {
+        let is_hex_prefix = matches!(c, 'x' | 'X') && size[start..i].trim() == "0";
+        if (matches!(c, 'x' | 'X' | '*')) && !is_hex_prefix {
+            factors.push(&size[start..i]);
+            start = i + 1;
+        }
+    }
+    factors.push(&size[start..]);
+    factors
+}
+
+// Parse a number with optional suffix
+fn parse_factor(factor: &str, bank: Option<u64>) -> Result<u64> {
+    let is_hex = factor.starts_with("0x") || factor.starts_with("0X");
+    let split = factor
+        .char_indices()
+        .find(|(i, c)| !(c.is_ascii_digit() || (is_hex && (*i == 1 || c.is_ascii_hexdigit()))))
+        .map_or(factor.len(), |(i, _)| i);
+    let (number, suffix) = factor.split_at(split);
+    let number = match number.strip_prefix("0x").or(number.strip_prefix("0X")) {
+        Some(hex) => u64::from_str_radix(hex, 16)?,
+        None if number.is_empty() => bail!("missing number in size literal: {factor}"),
+        None => number.parse::<u64>()?,
+    };
+    let unit = match suffix.trim().to_ascii_lowercase().as_str() {
+        "" => 1,
+        "k" | "kb" | "kib" => 1024,
+        "m" | "mb" | "mib" => 1024 * 1024,
+        "bank" | "banks" => match bank {
+            Some(bank) => bank,
+            None => bail!("bank count is not allowed for this size: {factor}"),
+        },
+        suffix => bail!("unknown size suffix: {suffix}"),
+    };
+    number
+        .checked_mul(unit)
+        .with_context(|| "too big size literal".to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rom_size_in_units() {
+        let size = PrgRomSize::from_bytes(128 * 1024).unwrap();
+        assert_eq!(size.units(), Some(8));
+        assert_eq!(size.exponent_multiplier(), None);
+        assert_eq!(size.bytes(), 128 * 1024);
+        assert_eq!(size.raw(), 8);
+        assert_eq!(String::from(size), "128K");
+        assert_eq!(String::from(ChrRomSize::from_units(128).unwrap()), "1M");
+    }
+
+    #[test]
+    fn rom_size_out_of_range() {
+        assert!(PrgRomSize::from_units(0xEFF).is_ok());
+        assert!(PrgRomSize::from_units(0xF00).is_err());
+        assert!(PrgRomSize::from_exponent_multiplier(61, 0).is_err());
+        assert!(PrgRomSize::from_exponent_multiplier(0, 4).is_err());
+        assert!(PrgRomSize::from_raw(0x1000).is_err());
+    }
+
+    #[test]
+    fn rom_size_serializes_into_string() {
+        let size = PrgRomSize::from_units(2).unwrap();
+        assert_eq!(serde_json::to_string(&size).unwrap(), r#""32K""#);
+        assert_eq!(
+            serde_json::from_str::<PrgRomSize>(r#""32K""#).unwrap(),
+            size
+        );
+        assert!(serde_json::from_str::<PrgRomSize>(r#""33K""#).is_err());
+    }
+
+    #[test]
+    fn ram_size() {
+        let size = RamSize::from_bytes(8 * 1024).unwrap();
+        assert_eq!(size.shift(), 7);
+        assert_eq!(String::from(size), "8K");
+        assert_eq!(String::from(RamSize::from_shift(1).unwrap()), "128");
+        assert_eq!(RamSize::from_bytes(0).unwrap().shift(), 0);
+        assert!(RamSize::from_bytes(64).is_err());
+        assert!(RamSize::from_shift(0x10).is_err());
+    }
+
+    #[test]
+    fn size_literals() {
+        let bytes = |literal| parse_size_as_byte(literal, Some(16 * 1024)).unwrap();
+        assert_eq!(bytes("0x40000"), 256 * 1024);
+        assert_eq!(bytes("0X10K"), 16 * 1024);
+        assert_eq!(bytes("8 banks"), 128 * 1024);
+        assert_eq!(bytes("1 bank"), 16 * 1024);
+        assert_eq!(bytes("8x16K"), 128 * 1024);
+        assert_eq!(bytes("2 * 4KiB"), 8 * 1024);
+        assert_eq!(bytes("16kb"), 16 * 1024);
+        assert_eq!(bytes("1mib"), 1024 * 1024);
+        assert_eq!(bytes("512"), 512);
+    }
+
+    #[test]
+    fn invalid_size_literals() {
+        assert!(parse_size_as_byte("8 banks", None).is_err());
+        assert!(parse_size_as_byte("3Q", None).is_err());
+        assert!(parse_size_as_byte("K", None).is_err());
+        assert!(parse_size_as_byte("0xG", None).is_err());
+        assert!(parse_size_as_byte("0x10000000000 x 0x10000000", None).is_err());
+    }
+
+    #[test]
+    fn nearest_sizes_in_error() {
+        let error = RamSize::parse_as("3K", "PRG-RAM").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "3K PRG-RAM is not a power-of-two shift; nearest: 2K, 4K"
+        );
+        let error = PrgRomSize::parse_as("100000", "PRG-ROM").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "100000 PRG-ROM is neither a multiple of 16K nor exponent-multiplier; nearest: 96K, 112K"
+        );
+        let error = RamSize::parse_as("8 banks", "CHR-RAM").unwrap_err();
+        assert!(
+            error
+                .to_string()
+                .starts_with("invalid CHR-RAM size literal 8 banks")
+        );
+    }
+
+    #[test]
+    fn exponent_multiplier_notation() {
+        let size = PrgRomSize::from_raw(0xF00 | 20 << 2 | 1).unwrap();
+        assert_eq!(size.exponent_multiplier(), Some((20, 1)));
+        assert_eq!(size.bytes(), 3 * 1024 * 1024);
+        assert_eq!(size.units(), None);
+        assert_eq!(size.raw(), 0xF51);
+        // Units are preferred, and exponent-multiplier is used only for the other sizes
+        assert_eq!(
+            PrgRomSize::from_bytes(3 * 1024 * 1024).unwrap().units(),
+            Some(192)
+        );
+        let size = PrgRomSize::from_bytes(7 * 1024).unwrap();
+        assert_eq!(size.exponent_multiplier(), Some((10, 3)));
+        assert!(PrgRomSize::from_bytes(9 * 1024).is_err());
+    }
+
+    #[test]
+    fn exponent_multiplier_in_json() {
+        let json = r#"{"exponent":20,"multiplier":1}"#;
+        let size: PrgRomSize = serde_json::from_str(json).unwrap();
+        assert_eq!(size.bytes(), 3 * 1024 * 1024);
+        assert_eq!(serde_json::to_string(&size).unwrap(), json);
+        assert!(serde_json::from_str::<PrgRomSize>(r#"{"exponent":20,"multiplier":4}"#).is_err());
+    }
+
+    #[test]
+    fn converts_between_notations() {
+        let size = ChrRomSize::from_units(4).unwrap();
+        let converted = size.to_exponent_multiplier().unwrap();
+        assert_eq!(converted.exponent_multiplier(), Some((15, 0)));
+        assert_eq!(converted.to_units().unwrap(), size);
+        let odd = ChrRomSize::from_exponent_multiplier(10, 0).unwrap();
+        assert!(odd.to_units().is_err());
+    }
+}
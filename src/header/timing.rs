@@ -1,7 +1,9 @@
-use anyhow::{Error, bail};
+use super::diagnostic::did_you_mean;
+use super::name::{from_name, names};
+use anyhow::{Error, anyhow};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(try_from = "String", into = "String")]
 pub enum Timing {
     RP2C02,
@@ -10,17 +12,90 @@ pub enum Timing {
     UA6538,
 }
 
+impl Timing {
+    /// All variants in declaration order
+    pub const ALL: [Timing; 4] = [
+        Timing::RP2C02,
+        Timing::RP2C07,
+        Timing::Multiple,
+        Timing::UA6538,
+    ];
+
+    /// Alternative names accepted in addition to the canonical ones
+    pub const ALIASES: [(&str, Timing); 4] = [
+        ("NTSC", Timing::RP2C02),
+        ("PAL", Timing::RP2C07),
+        ("Dendy", Timing::UA6538),
+        ("Multi-region", Timing::Multiple),
+    ];
+}
+
+/// Clock and frame metadata of a timing
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimingInfo {
+    /// CPU clock in Hz
+    pub cpu_clock: f64,
+    /// PPU dots per scanline
+    pub ppu_dots: u32,
+    /// Scanlines per frame
+    pub scanlines: u32,
+    /// Frames per second
+    pub frame_rate: f64,
+    /// PPU clocks per CPU clock
+    pub cpu_ppu_ratio: f64,
+}
+
+/// Master clock of NTSC consoles in Hz
+const NTSC_MASTER_CLOCK: f64 = 236_250_000.0 / 11.0;
+
+/// Master clock of PAL consoles and Dendy in Hz
+const PAL_MASTER_CLOCK: f64 = 26_601_712.5;
+
+impl Timing {
+    /// Returns name of the region of the timing
+    pub fn region(self) -> &'static str {
+        match self {
+            Timing::RP2C02 => "NTSC",
+            Timing::RP2C07 => "PAL",
+            Timing::Multiple => "Multi-region",
+            Timing::UA6538 => "Dendy",
+        }
+    }
+
+    /// Returns clock and frame metadata, which is the NTSC one for multi-region
+    pub fn info(self) -> TimingInfo {
+        // Master clock, its dividers for CPU and PPU, and scanlines
+        let (master_clock, cpu_divider, ppu_divider, scanlines) = match self {
+            Timing::RP2C02 | Timing::Multiple => (NTSC_MASTER_CLOCK, 12.0, 4.0, 262),
+            Timing::RP2C07 => (PAL_MASTER_CLOCK, 16.0, 5.0, 312),
+            Timing::UA6538 => (PAL_MASTER_CLOCK, 15.0, 5.0, 312),
+        };
+        let ppu_clock = master_clock / ppu_divider;
+        // NTSC PPU skips a dot every other frame when rendering
+        let dots_per_frame = match self {
+            Timing::RP2C02 | Timing::Multiple => 341.0 * scanlines as f64 - 0.5,
+            _ => 341.0 * scanlines as f64,
+        };
+        TimingInfo {
+            cpu_clock: master_clock / cpu_divider,
+            ppu_dots: 341,
+            scanlines,
+            frame_rate: ppu_clock / dots_per_frame,
+            cpu_ppu_ratio: cpu_divider / ppu_divider,
+        }
+    }
+}
+
 impl TryFrom<String> for Timing {
     type Error = Error;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        match value.as_str() {
-            "RP2C02" => Ok(Self::RP2C02),
-            "RP2C07" => Ok(Self::RP2C07),
-            "Multiple" => Ok(Self::Multiple),
-            "UA6538" => Ok(Self::UA6538),
-            _ => bail!("invalid timing name: {value}"),
-        }
+        from_name(&value, &Timing::ALL, &Timing::ALIASES).ok_or_else(|| {
+            anyhow!(
+                "invalid timing name: {value}{}",
+                did_you_mean(&value, names(&Timing::ALL, &Timing::ALIASES))
+            )
+        })
     }
 }
 
@@ -35,3 +110,27 @@ impl From<Timing> for String {
         value.to_string()
     }
 }
+
+/// Discriminant written in the header, which is defined in `codec::variants`
+impl From<Timing> for u8 {
+    fn from(value: Timing) -> Self {
+        use crate::codec::variants::timing;
+        match value {
+            Timing::RP2C02 => timing::RP2C02,
+            Timing::RP2C07 => timing::RP2C07,
+            Timing::Multiple => timing::Multiple,
+            Timing::UA6538 => timing::UA6538,
+        }
+    }
+}
+
+impl TryFrom<u8> for Timing {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Timing::ALL
+            .into_iter()
+            .find(|variant| u8::from(*variant) == value)
+            .ok_or_else(|| anyhow!("invalid timing type: {value}"))
+    }
+}
@@ -0,0 +1,261 @@
+use super::Header;
+use super::console::Console;
+use super::diagnostic::FieldError;
+use super::mirroring::Mirroring;
+use super::size::{ChrRomSize, PrgRomSize, RamSize};
+use super::timing::Timing;
+use super::vs_system::{hardware::VsSystemHardware, ppu::VsSystemPPU};
+use crate::codec;
+use anyhow::{Error, Result, bail};
+
+/// Read-only view of NES 2.0 file header, whose fields are computed on demand
+#[derive(Debug, Clone, Copy)]
+pub struct HeaderView<'a> {
+    bytes: &'a [u8; 16],
+}
+
+/// Mutable view of NES 2.0 file header, whose setters write only the bits of the field
+#[derive(Debug)]
+pub struct HeaderViewMut<'a> {
+    bytes: &'a mut [u8; 16],
+}
+
+impl<'a> HeaderView<'a> {
+    /// Wrap the bytes after checking NES 2.0 identifiers
+    pub fn new(bytes: &'a [u8; 16]) -> Result<Self> {
+        codec::check(bytes)?;
+        Ok(HeaderView { bytes })
+    }
+
+    pub fn bytes(&self) -> &'a [u8; 16] {
+        self.bytes
+    }
+
+    pub fn mapper(&self) -> u64 {
+        codec::mapper(self.bytes) as u64
+    }
+
+    pub fn submapper(&self) -> u64 {
+        codec::submapper(self.bytes) as u64
+    }
+
+    pub fn mirroring(&self) -> Mirroring {
+        in_range(codec::mirroring(self.bytes))
+    }
+
+    pub fn battery(&self) -> bool {
+        codec::battery(self.bytes)
+    }
+
+    pub fn trainer(&self) -> bool {
+        codec::trainer(self.bytes)
+    }
+
+    pub fn console(&self) -> Console {
+        in_range(codec::console(self.bytes))
+    }
+
+    pub fn timing(&self) -> Timing {
+        in_range(codec::timing(self.bytes))
+    }
+
+    /// Returns PPU type, which is meaningful only for Vs System
+    pub fn vs_system_ppu(&self) -> VsSystemPPU {
+        in_range(codec::vs_system_ppu(self.bytes))
+    }
+
+    /// Returns hardware type, which is meaningful only for Vs System
+    pub fn vs_system_hardware(&self) -> Result<VsSystemHardware> {
+        VsSystemHardware::try_from(codec::vs_system_hardware(self.bytes))
+    }
+
+    pub fn prg_rom_size(&self) -> Result<PrgRomSize> {
+        PrgRomSize::from_raw(codec::prg_rom_size(self.bytes))
+    }
+
+    pub fn prg_ram_size(&self) -> Result<RamSize> {
+        RamSize::from_shift(codec::prg_ram_shift(self.bytes))
+    }
+
+    pub fn prg_nvram_size(&self) -> Result<RamSize> {
+        RamSize::from_shift(codec::prg_nvram_shift(self.bytes))
+    }
+
+    pub fn chr_rom_size(&self) -> Result<ChrRomSize> {
+        ChrRomSize::from_raw(codec::chr_rom_size(self.bytes))
+    }
+
+    pub fn chr_ram_size(&self) -> Result<RamSize> {
+        RamSize::from_shift(codec::chr_ram_shift(self.bytes))
+    }
+
+    pub fn chr_nvram_size(&self) -> Result<RamSize> {
+        RamSize::from_shift(codec::chr_nvram_shift(self.bytes))
+    }
+
+    /// Returns `Header` which has every field of the view
+    pub fn to_header(&self) -> Result<Header> {
+        let console = self.console();
+        let vs_system = console == Console::VsSystem;
+        Ok(Header {
+            mapper: self.mapper(),
+            submapper: Some(self.submapper()),
+            mirroring: Some(self.mirroring()),
+            battery: Some(self.battery()),
+            trainer: Some(self.trainer()),
+            console: Some(console),
+            timing: Some(self.timing()),
+            vs_system_ppu: vs_system.then(|| self.vs_system_ppu()),
+            vs_system_hardware: vs_system.then(|| self.vs_system_hardware()).transpose()?,
+            prg_rom_size: self.prg_rom_size()?,
+            prg_ram_size: Some(self.prg_ram_size()?),
+            prg_nvram_size: Some(self.prg_nvram_size()?),
+            chr_rom_size: self.chr_rom_size()?,
+            chr_ram_size: Some(self.chr_ram_size()?),
+            chr_nvram_size: Some(self.chr_nvram_size()?),
+        })
+    }
+}
+
+impl<'a> HeaderViewMut<'a> {
+    /// Wrap the bytes after checking NES 2.0 identifiers
+    pub fn new(bytes: &'a mut [u8; 16]) -> Result<Self> {
+        codec::check(bytes)?;
+        Ok(HeaderViewMut { bytes })
+    }
+
+    /// Wrap the bytes after writing NES 2.0 identifiers into them
+    pub fn init(bytes: &'a mut [u8; 16]) -> Self {
+        codec::init(bytes);
+        HeaderViewMut { bytes }
+    }
+
+    /// Returns read-only view to use getters
+    pub fn view(&self) -> HeaderView<'_> {
+        HeaderView { bytes: self.bytes }
+    }
+
+    pub fn set_mapper(&mut self, mapper: u64) -> Result<()> {
+        if mapper > 0xFFF {
+            bail!(FieldError::new(
+                "mapper",
+                format!("mapper number is too large: {mapper}")
+            ))
+        }
+        Ok(codec::set_mapper(self.bytes, mapper as u16)?)
+    }
+
+    pub fn set_submapper(&mut self, submapper: u64) -> Result<()> {
+        if submapper > 0xF {
+            bail!(FieldError::new(
+                "submapper",
+                format!("submapper number is too large: {submapper}")
+            ))
+        }
+        Ok(codec::set_submapper(self.bytes, submapper as u8)?)
+    }
+
+    pub fn set_mirroring(&mut self, mirroring: Mirroring) {
+        // Discriminants of enums are always in range
+        let _ = codec::set_mirroring(self.bytes, mirroring.into());
+    }
+
+    pub fn set_battery(&mut self, battery: bool) {
+        codec::set_battery(self.bytes, battery);
+    }
+
+    pub fn set_trainer(&mut self, trainer: bool) {
+        codec::set_trainer(self.bytes, trainer);
+    }
+
+    /// Set console type, which shares byte 13 with Vs System types. Vs System types are
+    /// cleared when the type changes, so set them after the console type.
+    pub fn set_console(&mut self, console: Console) {
+        let _ = codec::set_console(self.bytes, console.into());
+    }
+
+    pub fn set_timing(&mut self, timing: Timing) {
+        let _ = codec::set_timing(self.bytes, timing.into());
+    }
+
+    pub fn set_vs_system_ppu(&mut self, ppu: VsSystemPPU) {
+        let _ = codec::set_vs_system_ppu(self.bytes, ppu.into());
+    }
+
+    pub fn set_vs_system_hardware(&mut self, hardware: VsSystemHardware) {
+        let _ = codec::set_vs_system_hardware(self.bytes, hardware.into());
+    }
+
+    pub fn set_prg_rom_size(&mut self, size: PrgRomSize) -> Result<()> {
+        Ok(codec::set_prg_rom_size(self.bytes, size.raw())?)
+    }
+
+    pub fn set_prg_ram_size(&mut self, size: RamSize) -> Result<()> {
+        Ok(codec::set_prg_ram_shift(self.bytes, size.shift())?)
+    }
+
+    pub fn set_prg_nvram_size(&mut self, size: RamSize) -> Result<()> {
+        Ok(codec::set_prg_nvram_shift(self.bytes, size.shift())?)
+    }
+
+    pub fn set_chr_rom_size(&mut self, size: ChrRomSize) -> Result<()> {
+        Ok(codec::set_chr_rom_size(self.bytes, size.raw())?)
+    }
+
+    pub fn set_chr_ram_size(&mut self, size: RamSize) -> Result<()> {
+        Ok(codec::set_chr_ram_shift(self.bytes, size.shift())?)
+    }
+
+    pub fn set_chr_nvram_size(&mut self, size: RamSize) -> Result<()> {
+        Ok(codec::set_chr_nvram_shift(self.bytes, size.shift())?)
+    }
+}
+
+// Every discriminant which fits in the bits of the field has a variant
+fn in_range<T: TryFrom<u8, Error = Error>>(discriminant: u8) -> T {
+    T::try_from(discriminant).expect("discriminant read from the header is in range")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_variant_round_trips() {
+        let mut bytes = [0; 16];
+        let mut view = HeaderViewMut::init(&mut bytes);
+        for mirroring in Mirroring::ALL {
+            view.set_mirroring(mirroring);
+            assert_eq!(view.view().mirroring(), mirroring);
+        }
+        for console in Console::ALL {
+            view.set_console(console);
+            assert_eq!(view.view().console(), console);
+        }
+        for timing in Timing::ALL {
+            view.set_timing(timing);
+            assert_eq!(view.view().timing(), timing);
+        }
+        view.set_console(Console::VsSystem);
+        for ppu in VsSystemPPU::ALL {
+            view.set_vs_system_ppu(ppu);
+            assert_eq!(view.view().vs_system_ppu(), ppu);
+        }
+        for hardware in VsSystemHardware::ALL {
+            view.set_vs_system_hardware(hardware);
+            assert_eq!(view.view().vs_system_hardware().unwrap(), hardware);
+        }
+    }
+
+    #[test]
+    fn invalid_vs_system_hardware() {
+        let mut bytes = [0; 16];
+        codec::init(&mut bytes);
+        bytes[13] = 0x70;
+        let view = HeaderView::new(&bytes).unwrap();
+        assert_eq!(
+            view.vs_system_hardware().unwrap_err().to_string(),
+            "invalid vs system hardware type: 7"
+        );
+    }
+}
@@ -1,7 +1,9 @@
-use anyhow::{Error, bail};
+use crate::header::diagnostic::did_you_mean;
+use crate::header::name::{from_name, names};
+use anyhow::{Error, anyhow};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(try_from = "String", into = "String")]
 pub enum VsSystemHardware {
     UnisystemNormal,
@@ -13,20 +15,29 @@ pub enum VsSystemHardware {
     DualSystemRaidOnBungelingBay,
 }
 
+impl VsSystemHardware {
+    /// All variants in declaration order
+    pub const ALL: [VsSystemHardware; 7] = [
+        VsSystemHardware::UnisystemNormal,
+        VsSystemHardware::UnisystemRBIBaseball,
+        VsSystemHardware::UnisystemTKOBoxing,
+        VsSystemHardware::UnisystemSuperXevious,
+        VsSystemHardware::UnisystemIceClimberJapan,
+        VsSystemHardware::DualSystemNormal,
+        VsSystemHardware::DualSystemRaidOnBungelingBay,
+    ];
+}
+
 impl TryFrom<String> for VsSystemHardware {
     type Error = Error;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        match value.as_str() {
-            "Default" => Ok(VsSystemHardware::UnisystemNormal),
-            "RBI Baseball" => Ok(VsSystemHardware::UnisystemRBIBaseball),
-            "TKO Boxing" => Ok(VsSystemHardware::UnisystemTKOBoxing),
-            "Super Xevious" => Ok(VsSystemHardware::UnisystemSuperXevious),
-            "Ice Climber Japan" => Ok(VsSystemHardware::UnisystemIceClimberJapan),
-            "Dual System" => Ok(VsSystemHardware::DualSystemNormal),
-            "Raid on Bungeling Bay" => Ok(VsSystemHardware::DualSystemRaidOnBungelingBay),
-            _ => bail!("invalid vs system hardware name: {value}"),
-        }
+        from_name(&value, &VsSystemHardware::ALL, &[]).ok_or_else(|| {
+            anyhow!(
+                "invalid vs system hardware name: {value}{}",
+                did_you_mean(&value, names(&VsSystemHardware::ALL, &[]))
+            )
+        })
     }
 }
 
@@ -44,3 +55,34 @@ impl From<VsSystemHardware> for String {
         value.to_string()
     }
 }
+
+/// Discriminant written in the header, which is defined in `codec::variants`
+impl From<VsSystemHardware> for u8 {
+    fn from(value: VsSystemHardware) -> Self {
+        use crate::codec::variants::vs_system_hardware;
+        match value {
+            VsSystemHardware::UnisystemNormal => vs_system_hardware::UnisystemNormal,
+            VsSystemHardware::UnisystemRBIBaseball => vs_system_hardware::UnisystemRBIBaseball,
+            VsSystemHardware::UnisystemTKOBoxing => vs_system_hardware::UnisystemTKOBoxing,
+            VsSystemHardware::UnisystemSuperXevious => vs_system_hardware::UnisystemSuperXevious,
+            VsSystemHardware::UnisystemIceClimberJapan => {
+                vs_system_hardware::UnisystemIceClimberJapan
+            }
+            VsSystemHardware::DualSystemNormal => vs_system_hardware::DualSystemNormal,
+            VsSystemHardware::DualSystemRaidOnBungelingBay => {
+                vs_system_hardware::DualSystemRaidOnBungelingBay
+            }
+        }
+    }
+}
+
+impl TryFrom<u8> for VsSystemHardware {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        VsSystemHardware::ALL
+            .into_iter()
+            .find(|variant| u8::from(*variant) == value)
+            .ok_or_else(|| anyhow!("invalid vs system hardware type: {value}"))
+    }
+}
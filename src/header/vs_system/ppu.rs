@@ -1,7 +1,9 @@
-use anyhow::{Error, bail};
+use crate::header::diagnostic::did_you_mean;
+use crate::header::name::{from_name, names};
+use anyhow::{Error, anyhow};
 use serde::{Deserialize, Serialize};
 
-#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
 #[serde(try_from = "String", into = "String")]
 pub enum VsSystemPPU {
     Any,
@@ -22,29 +24,38 @@ pub enum VsSystemPPU {
     Reserved0F,
 }
 
+impl VsSystemPPU {
+    /// All variants in declaration order
+    pub const ALL: [VsSystemPPU; 16] = [
+        VsSystemPPU::Any,
+        VsSystemPPU::Reserved01,
+        VsSystemPPU::RP2C04_0001,
+        VsSystemPPU::RP2C04_0002,
+        VsSystemPPU::RP2C04_0003,
+        VsSystemPPU::RP2C04_0004,
+        VsSystemPPU::Reserved06,
+        VsSystemPPU::Reserved07,
+        VsSystemPPU::RC2C05_01,
+        VsSystemPPU::RC2C05_02,
+        VsSystemPPU::RC2C05_03,
+        VsSystemPPU::RC2C05_04,
+        VsSystemPPU::Reserved0C,
+        VsSystemPPU::Reserved0D,
+        VsSystemPPU::Reserved0E,
+        VsSystemPPU::Reserved0F,
+    ];
+}
+
 impl TryFrom<String> for VsSystemPPU {
     type Error = Error;
 
     fn try_from(value: String) -> Result<Self, Self::Error> {
-        match value.as_str() {
-            "Any" => Ok(VsSystemPPU::Any),
-            "Reserved01" => Ok(VsSystemPPU::Reserved01),
-            "RP2C04-0001" => Ok(VsSystemPPU::RP2C04_0001),
-            "RP2C04-0002" => Ok(VsSystemPPU::RP2C04_0002),
-            "RP2C04-0003" => Ok(VsSystemPPU::RP2C04_0003),
-            "RP2C04-0004" => Ok(VsSystemPPU::RP2C04_0004),
-            "Reserved06" => Ok(VsSystemPPU::Reserved06),
-            "Reserved07" => Ok(VsSystemPPU::Reserved07),
-            "RC2C05-01" => Ok(VsSystemPPU::RC2C05_01),
-            "RC2C05-02" => Ok(VsSystemPPU::RC2C05_02),
-            "RC2C05-03" => Ok(VsSystemPPU::RC2C05_03),
-            "RC2C05-04" => Ok(VsSystemPPU::RC2C05_04),
-            "Reserved0C" => Ok(VsSystemPPU::Reserved0C),
-            "Reserved0D" => Ok(VsSystemPPU::Reserved0D),
-            "Reserved0E" => Ok(VsSystemPPU::Reserved0E),
-            "Reserved0F" => Ok(VsSystemPPU::Reserved0F),
-            _ => bail!("invalid vs system ppu name: {value}"),
-        }
+        from_name(&value, &VsSystemPPU::ALL, &[]).ok_or_else(|| {
+            anyhow!(
+                "invalid vs system ppu name: {value}{}",
+                did_you_mean(&value, names(&VsSystemPPU::ALL, &[]))
+            )
+        })
     }
 }
 
@@ -71,3 +82,39 @@ impl From<VsSystemPPU> for String {
         value.to_string()
     }
 }
+
+/// Discriminant written in the header, which is defined in `codec::variants`
+impl From<VsSystemPPU> for u8 {
+    fn from(value: VsSystemPPU) -> Self {
+        use crate::codec::variants::vs_system_ppu;
+        match value {
+            VsSystemPPU::Any => vs_system_ppu::Any,
+            VsSystemPPU::Reserved01 => vs_system_ppu::Reserved01,
+            VsSystemPPU::RP2C04_0001 => vs_system_ppu::RP2C04_0001,
+            VsSystemPPU::RP2C04_0002 => vs_system_ppu::RP2C04_0002,
+            VsSystemPPU::RP2C04_0003 => vs_system_ppu::RP2C04_0003,
+            VsSystemPPU::RP2C04_0004 => vs_system_ppu::RP2C04_0004,
+            VsSystemPPU::Reserved06 => vs_system_ppu::Reserved06,
+            VsSystemPPU::Reserved07 => vs_system_ppu::Reserved07,
+            VsSystemPPU::RC2C05_01 => vs_system_ppu::RC2C05_01,
+            VsSystemPPU::RC2C05_02 => vs_system_ppu::RC2C05_02,
+            VsSystemPPU::RC2C05_03 => vs_system_ppu::RC2C05_03,
+            VsSystemPPU::RC2C05_04 => vs_system_ppu::RC2C05_04,
+            VsSystemPPU::Reserved0C => vs_system_ppu::Reserved0C,
+            VsSystemPPU::Reserved0D => vs_system_ppu::Reserved0D,
+            VsSystemPPU::Reserved0E => vs_system_ppu::Reserved0E,
+            VsSystemPPU::Reserved0F => vs_system_ppu::Reserved0F,
+        }
+    }
+}
+
+impl TryFrom<u8> for VsSystemPPU {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        VsSystemPPU::ALL
+            .into_iter()
+            .find(|variant| u8::from(*variant) == value)
+            .ok_or_else(|| anyhow!("invalid vs system ppu type: {value}"))
+    }
+}
@@ -0,0 +1,179 @@
+//! Journal of header edits, a JSON object per line, which allows undoing them.
+
+use crate::rewrite::{self, Rewrite};
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+
+/// Edit of a header, written as a line of journal
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct Entry {
+    /// Absolute path of the edited file
+    pub path: PathBuf,
+    /// Header before the edit, written in hex
+    #[serde(serialize_with = "to_hex", deserialize_with = "from_hex")]
+    pub old: [u8; 16],
+    /// Header after the edit, written in hex
+    #[serde(serialize_with = "to_hex", deserialize_with = "from_hex")]
+    pub new: [u8; 16],
+    /// SHA-256 of the whole file after the edit, written in hex
+    #[serde(serialize_with = "to_hex", deserialize_with = "from_hex")]
+    pub hash: [u8; 32],
+}
+
+impl Entry {
+    /// Restore the old header after checking the file hasn't changed since the edit.
+    ///
+    /// The restore isn't recorded, since the entry is read from a journal being undone.
+    pub fn undo(&self, backup: bool) -> Result<()> {
+        let file = Rewrite::open(&self.path)?;
+        if file.hash() != self.hash {
+            bail!("file has been modified since the edit");
+        }
+        let mut bytes = file.bytes().to_vec();
+        bytes[..16].copy_from_slice(&self.old);
+        file.commit(&bytes, backup, None)
+    }
+}
+
+/// Journal opened for appending entries
+#[derive(Debug)]
+pub struct Journal {
+    file: File,
+}
+
+impl Journal {
+    /// Open the journal for appending, creating it if it doesn't exist
+    pub fn append<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open journal: {}", path.display()))?;
+        Ok(Journal { file })
+    }
+
+    /// Record an edit of the file, whose header was replaced by the bytes.
+    ///
+    /// `Rewrite::commit` records its edit, so this is for files written in other ways.
+    pub fn record<P: AsRef<Path>>(&mut self, path: P, old: &[u8], bytes: &[u8]) -> Result<()> {
+        let path = path.as_ref();
+        let header = |bytes: &[u8]| -> Result<[u8; 16]> {
+            bytes
+                .get(..16)
+                .and_then(|header| header.try_into().ok())
+                .context("file is shorter than header")
+        };
+        let entry = Entry {
+            // Absolute path lets undo work from another directory
+            path: path
+                .canonicalize()
+                .with_context(|| format!("failed to resolve path: {}", path.display()))?,
+            old: header(old)?,
+            new: header(bytes)?,
+            hash: rewrite::hash(bytes),
+        };
+        // Written line by line so that edits before a failure are kept
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes())?;
+        self.file.flush()?;
+        Ok(())
+    }
+}
+
+/// Read entries of the journal in the order of the edits
+pub fn read<P: AsRef<Path>>(path: P) -> Result<Vec<Entry>> {
+    let path = path.as_ref();
+    let file =
+        File::open(path).with_context(|| format!("failed to open journal: {}", path.display()))?;
+    let mut entries = Vec::new();
+    for (number, line) in BufReader::new(file).lines().enumerate() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry = serde_json::from_str(&line)
+            .map_err(|e| anyhow!("{}:{}: invalid entry: {e}", path.display(), number + 1))?;
+        entries.push(entry);
+    }
+    Ok(entries)
+}
+
+fn to_hex<S: Serializer>(bytes: &[u8], serializer: S) -> Result<S::Ok, S::Error> {
+    let hex: String = bytes.iter().map(|byte| format!("{byte:02x}")).collect();
+    serializer.serialize_str(&hex)
+}
+
+fn from_hex<'de, D: Deserializer<'de>, const N: usize>(
+    deserializer: D,
+) -> Result<[u8; N], D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    let invalid = || serde::de::Error::custom(format!("invalid hex of {N} bytes: {hex}"));
+    if hex.len() != N * 2 || !hex.is_ascii() {
+        return Err(invalid());
+    }
+    let mut bytes = [0; N];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[i * 2..i * 2 + 2], 16).map_err(|_| invalid())?;
+    }
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use tempfile::tempdir;
+
+    const OLD: &[u8; 20] = b"NES\x1a\x02\x01\x00\x08\0\0\0\0\0\0\0\0data";
+    const NEW: &[u8; 20] = b"NES\x1a\x02\x01\x10\x08\0\0\0\0\0\0\0\0data";
+
+    #[test]
+    fn undo_restores_old_header() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("game.nes");
+        let journal_path = directory.path().join("journal.jsonl");
+        fs::write(&path, OLD).unwrap();
+        let mut journal = Journal::append(&journal_path).unwrap();
+        let file = Rewrite::open(&path).unwrap();
+        file.commit(NEW, false, Some(&mut journal)).unwrap();
+
+        let entries = read(&journal_path).unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path, fs::canonicalize(&path).unwrap());
+        assert_eq!(entries[0].old[..], OLD[..16]);
+        assert_eq!(entries[0].new[..], NEW[..16]);
+        entries[0].undo(false).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), OLD);
+    }
+
+    #[test]
+    fn undo_refuses_modified_file() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("game.nes");
+        let journal_path = directory.path().join("journal.jsonl");
+        fs::write(&path, OLD).unwrap();
+        let mut journal = Journal::append(&journal_path).unwrap();
+        journal.record(&path, OLD, NEW).unwrap();
+
+        // The file still has the old header, which doesn't match the hash of the edit
+        let entries = read(&journal_path).unwrap();
+        let error = entries[0].undo(false).unwrap_err();
+        assert_eq!(error.to_string(), "file has been modified since the edit");
+        assert_eq!(fs::read(&path).unwrap(), OLD);
+    }
+
+    #[test]
+    fn invalid_entry() {
+        let directory = tempdir().unwrap();
+        let journal_path = directory.path().join("journal.jsonl");
+        let line = r#"{"path":"/game.nes","old":"00","new":"00","hash":"00"}"#;
+        fs::write(&journal_path, format!("\n{line}\n")).unwrap();
+        let error = read(&journal_path).unwrap_err().to_string();
+        assert!(error.contains(":2: invalid entry: invalid hex of 16 bytes: 00"));
+    }
+}
@@ -1,9 +1,24 @@
-mod header;
-
-use anyhow::{Context, Error};
-use clap::{Parser, Subcommand};
-use header::Header;
-use std::{fs::File, path::PathBuf, process::exit, str::FromStr};
+use anyhow::{Context, Error, anyhow, bail};
+use clap::{Parser, Subcommand, ValueEnum};
+use neshdr::codec::{self, Format};
+use neshdr::header::scan::Record;
+use neshdr::header::{
+    Header, MapperNotation, asm::AsmFormat, code::CodeFormat, diagnostic::Diagnostic, edit,
+    format::DefinitionFormat, size::RomSizeEncoding, timing::Timing,
+};
+use neshdr::journal::{self, Journal};
+use neshdr::rewrite::{self, Rewrite};
+use rayon::prelude::*;
+use serde_json::Value;
+use std::{
+    fs,
+    fs::File,
+    io::{self, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::exit,
+    str::FromStr,
+};
+use walkdir::WalkDir;
 
 #[derive(Parser)]
 #[command(name = "neshdr", version)]
@@ -11,66 +26,750 @@ use std::{fs::File, path::PathBuf, process::exit, str::FromStr};
 struct Cli {
     #[command(subcommand)]
     command: Commands,
+
+    #[arg(long, global = true, value_enum, default_value_t = MessageFormat::Human)]
+    #[arg(help = "Format of error messages")]
+    message_format: MessageFormat,
 }
 
 #[derive(Subcommand)]
 enum Commands {
     #[command(about = "Generate NES 2.0 file header from definition file", long_about = None)]
+    #[command(after_help = "Paths can be - for stdin or stdout")]
     Gen {
         input: Option<PathBuf>,
 
         #[arg(short, long)]
         output: Option<PathBuf>,
+
+        #[arg(long, value_enum)]
+        #[arg(help = "Force encoding of ROM sizes instead of the one written in definition")]
+        rom_size_encoding: Option<RomSizeEncodings>,
+
+        #[arg(short, long, value_enum, default_value_t = GenFormat::Binary)]
+        #[arg(help = "Format of generated header")]
+        format: GenFormat,
+
+        #[arg(long, value_enum)]
+        #[arg(help = "Format of definition file, detected from its extension by default")]
+        definition_format: Option<DefinitionFormats>,
+
+        #[arg(long, help = "Write binary header even to a terminal")]
+        force: bool,
     },
     #[command(about = "Dump NES 2.0 file header into definition file", long_about = None)]
+    #[command(after_help = "Paths can be - for stdin or stdout")]
     Dump {
         input: PathBuf,
 
+        #[arg(short, long)]
+        #[arg(help = "Output file, header.json by default, or - for stdout")]
+        output: Option<PathBuf>,
+
+        #[arg(long, value_enum, default_value_t = MapperNames::Number)]
+        #[arg(help = "How to write mapper and submapper")]
+        mapper_names: MapperNames,
+
+        #[arg(long, help = "Print known boards which match the header")]
+        boards: bool,
+
+        #[arg(long, value_enum)]
+        #[arg(help = "Format of output definition file, detected from its extension by default")]
+        definition_format: Option<DefinitionFormats>,
+
+        #[arg(long, value_enum)]
+        #[arg(
+            help = "Format of input definition file, detected from its extension, or JSON for stdin"
+        )]
+        format: Option<DefinitionFormats>,
+    },
+    #[command(about = "Print fields of NES 2.0 file header with timing metadata", long_about = None)]
+    #[command(after_help = "Paths can be - for stdin")]
+    Info { input: PathBuf },
+    #[command(about = "Print byte ranges of the sections of NES file", long_about = None)]
+    #[command(after_help = "Paths can be - for stdin")]
+    Layout { input: PathBuf },
+    #[command(about = "Import header block of assembly source into definition file", long_about = None)]
+    #[command(after_help = "Paths can be - for stdin or stdout")]
+    Import {
+        input: PathBuf,
+
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        #[arg(long, value_enum)]
+        #[arg(help = "Format of definition file, detected from its extension by default")]
+        definition_format: Option<DefinitionFormats>,
+    },
+    #[command(about = "Write JSON Schema of definition file", long_about = None)]
+    #[command(after_help = "Paths can be - for stdout")]
+    Schema {
         #[arg(short, long)]
         output: Option<PathBuf>,
     },
+    #[command(about = "Rewrite deprecated mapper numbers into canonical ones", long_about = None)]
+    Modernize {
+        #[arg(required = true)]
+        inputs: Vec<PathBuf>,
+
+        #[arg(
+            long,
+            help = "Rewrite canonical mapper numbers into deprecated ones instead"
+        )]
+        reverse: bool,
+
+        #[arg(
+            long,
+            help = "Keep the original file as <file>.bak, or <file>.bak.N if it exists"
+        )]
+        backup: bool,
+
+        #[arg(
+            long,
+            help = "Append the edits to the journal, which `undo` can revert"
+        )]
+        journal: Option<PathBuf>,
+    },
+    #[command(about = "Set fields of definition or .nes file in place", long_about = None)]
+    #[command(after_help = "Comments and layout of definition file are kept")]
+    Set {
+        input: PathBuf,
+
+        #[arg(required = true, help = "Fields to set as <field>=<value>")]
+        fields: Vec<String>,
+
+        #[arg(long, value_enum)]
+        #[arg(help = "Format of definition file, detected from its extension by default")]
+        definition_format: Option<DefinitionFormats>,
+
+        #[arg(
+            long,
+            help = "Keep the original file as <file>.bak, or <file>.bak.N if it exists"
+        )]
+        backup: bool,
+
+        #[arg(
+            long,
+            help = "Append the edit of .nes file to the journal, which `undo` can revert"
+        )]
+        journal: Option<PathBuf>,
+    },
+    #[command(about = "Read headers of .nes files in parallel into JSON lines", long_about = None)]
+    #[command(after_help = "Paths can be - for stdout")]
+    Scan {
+        #[arg(required = true)]
+        paths: Vec<PathBuf>,
+
+        #[arg(short, long, help = "Scan subdirectories too")]
+        recursive: bool,
+
+        #[arg(short, long, default_value = "-")]
+        output: PathBuf,
+    },
+    #[command(about = "Revert the edits recorded in journal, latest first", long_about = None)]
+    Undo {
+        journal: PathBuf,
+
+        #[arg(
+            long,
+            help = "Keep the edited file as <file>.bak, or <file>.bak.N if it exists"
+        )]
+        backup: bool,
+    },
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum MessageFormat {
+    #[value(help = "Messages with source snippets")]
+    Human,
+    #[value(help = "A JSON diagnostic per line")]
+    Json,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum MapperNames {
+    #[value(help = "Write numbers")]
+    Number,
+    #[value(help = "Write names instead of numbers if known")]
+    Name,
+    #[value(help = "Write numbers with mapper_name and submapper_name fields")]
+    Annotate,
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq, Eq)]
+enum GenFormat {
+    #[value(help = "Raw 16 bytes")]
+    Binary,
+    #[value(help = "ca65 .segment \"HEADER\" block")]
+    Ca65,
+    #[value(help = "asm6 db lines")]
+    Asm6,
+    #[value(help = "NESASM .db lines")]
+    Nesasm,
+    #[value(help = "NESASM .ines directives")]
+    NesasmInes,
+    #[value(help = "C header with uint8_t array and constants")]
+    C,
+    #[value(help = "Rust source with byte array and constants")]
+    Rust,
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum DefinitionFormats {
+    Json,
+    #[value(help = "JSON5, also accepts JSON with comments")]
+    Json5,
+    Toml,
+    Yaml,
+    Ron,
+}
+
+impl From<DefinitionFormats> for DefinitionFormat {
+    fn from(value: DefinitionFormats) -> Self {
+        match value {
+            DefinitionFormats::Json => DefinitionFormat::Json,
+            DefinitionFormats::Json5 => DefinitionFormat::Json5,
+            DefinitionFormats::Toml => DefinitionFormat::Toml,
+            DefinitionFormats::Yaml => DefinitionFormat::Yaml,
+            DefinitionFormats::Ron => DefinitionFormat::Ron,
+        }
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy)]
+enum RomSizeEncodings {
+    #[value(help = "Count of units if possible, exponent-multiplier otherwise")]
+    Auto,
+    #[value(help = "Count of units")]
+    Units,
+    #[value(help = "Exponent-multiplier")]
+    Exponent,
+}
+
+impl From<RomSizeEncodings> for RomSizeEncoding {
+    fn from(value: RomSizeEncodings) -> Self {
+        match value {
+            RomSizeEncodings::Auto => RomSizeEncoding::Auto,
+            RomSizeEncodings::Units => RomSizeEncoding::Units,
+            RomSizeEncodings::Exponent => RomSizeEncoding::ExponentMultiplier,
+        }
+    }
+}
+
+impl From<MapperNames> for MapperNotation {
+    fn from(value: MapperNames) -> Self {
+        match value {
+            MapperNames::Number => MapperNotation::Number,
+            MapperNames::Name => MapperNotation::Name,
+            MapperNames::Annotate => MapperNotation::Annotated,
+        }
+    }
 }
 
 fn main() {
     let cli = Cli::parse();
+    let message_format = cli.message_format;
     match parse_command(cli) {
         Ok(_) => (),
+        // Reader of piped output like `head` may exit early, which isn't an error
+        Err(e) if is_broken_pipe(&e) => (),
         Err(e) => {
-            eprintln!("{e}");
+            report(&e, message_format);
             exit(1);
         }
     }
 }
 
+fn is_broken_pipe(e: &Error) -> bool {
+    let io_error =
+        |e: &(dyn std::error::Error + 'static)| match e.downcast_ref::<serde_json::Error>() {
+            Some(e) => e.io_error_kind(),
+            None => e.downcast_ref::<io::Error>().map(io::Error::kind),
+        };
+    e.chain()
+        .any(|e| io_error(e) == Some(io::ErrorKind::BrokenPipe))
+}
+
+fn report(e: &Error, format: MessageFormat) {
+    match (format, e.downcast_ref::<Diagnostic>()) {
+        (MessageFormat::Human, _) => eprintln!("{e}"),
+        (MessageFormat::Json, Some(diagnostic)) => {
+            eprintln!("{}", serde_json::to_string(diagnostic).unwrap_or_default())
+        }
+        (MessageFormat::Json, None) => {
+            eprintln!("{}", serde_json::json!({ "message": e.to_string() }))
+        }
+    }
+}
+
 fn parse_command(cli: Cli) -> Result<(), Error> {
     match cli.command {
-        Commands::Gen { input, output } => {
+        Commands::Gen {
+            input,
+            output,
+            rom_size_encoding,
+            format,
+            definition_format,
+            force,
+        } => {
             let input = input.unwrap_or(PathBuf::from_str("header.json")?);
-            let output = output.unwrap_or(PathBuf::from_str("header.dat")?);
-            do_gen(input, output)?;
+            let output = match (output, format) {
+                (Some(output), _) => output,
+                (None, GenFormat::Binary) => PathBuf::from_str("header.dat")?,
+                (None, GenFormat::C) => PathBuf::from_str("header.h")?,
+                (None, GenFormat::Rust) => PathBuf::from_str("header.rs")?,
+                (None, _) => PathBuf::from_str("header.s")?,
+            };
+            let definition_format = definition_format_of(&input, definition_format);
+            let encoding = rom_size_encoding.map(Into::into);
+            do_gen(input, output, definition_format, encoding, format, force)?;
         }
-        Commands::Dump { input, output } => {
+        Commands::Dump {
+            input,
+            output,
+            mapper_names,
+            boards,
+            definition_format,
+            format,
+        } => {
             let output = output.unwrap_or(PathBuf::from_str("header.json")?);
-            do_dump(input, output)?;
+            let input_format = definition_format_of(&input, format);
+            let definition_format = definition_format_of(&output, definition_format);
+            do_dump(
+                input,
+                output,
+                input_format,
+                definition_format,
+                mapper_names.into(),
+                boards,
+            )?;
+        }
+        Commands::Info { input } => do_info(input)?,
+        Commands::Layout { input } => do_layout(input)?,
+        Commands::Import {
+            input,
+            output,
+            definition_format,
+        } => {
+            let output = output.unwrap_or(PathBuf::from_str("header.json")?);
+            let definition_format = definition_format_of(&output, definition_format);
+            do_import(input, output, definition_format)?;
+        }
+        Commands::Schema { output } => {
+            let output = output.unwrap_or(PathBuf::from_str("header.schema.json")?);
+            do_schema(output)?;
+        }
+        // Every file is tried so that all errors are reported at once
+        Commands::Modernize {
+            inputs,
+            reverse,
+            backup,
+            journal,
+        } => {
+            let mut journal = journal.map(Journal::append).transpose()?;
+            let mut failed = false;
+            for input in inputs {
+                let file = input.display().to_string();
+                if let Err(e) = do_modernize(input, reverse, backup, journal.as_mut()) {
+                    report(&Diagnostic::new(&file, e).into(), cli.message_format);
+                    failed = true;
+                }
+            }
+            if failed {
+                exit(1);
+            }
+        }
+        Commands::Set {
+            input,
+            fields,
+            definition_format,
+            backup,
+            journal,
+        } => {
+            let format = definition_format_of(&input, definition_format);
+            let mut journal = journal.map(Journal::append).transpose()?;
+            do_set(input, fields, format, backup, journal.as_mut())?;
+        }
+        Commands::Scan {
+            paths,
+            recursive,
+            output,
+        } => do_scan(paths, recursive, output)?,
+        // Files are restored in reverse order in case one was edited more than once
+        Commands::Undo { journal, backup } => {
+            let mut failed = false;
+            for entry in journal::read(&journal)?.iter().rev() {
+                let file = entry.path.display().to_string();
+                match entry.undo(backup) {
+                    Ok(()) => println!("{file}: restored"),
+                    Err(e) => {
+                        report(&Diagnostic::new(&file, e).into(), cli.message_format);
+                        failed = true;
+                    }
+                }
+            }
+            if failed {
+                exit(1);
+            }
+        }
+    }
+    Ok(())
+}
+
+// Use explicitly specified format, or detect it from extension of the path
+fn definition_format_of(path: &Path, format: Option<DefinitionFormats>) -> DefinitionFormat {
+    match format {
+        Some(format) => format.into(),
+        None => DefinitionFormat::from_path(path).unwrap_or(DefinitionFormat::Json),
+    }
+}
+
+// Path which stands for stdin or stdout
+fn is_stdio(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+// Name of the path in messages
+fn name_of(path: &Path) -> String {
+    match is_stdio(path) {
+        true => "<stdin>".to_string(),
+        false => path.display().to_string(),
+    }
+}
+
+fn read_input(path: &Path) -> Result<Vec<u8>, Error> {
+    if !is_stdio(path) {
+        return fs::read(path)
+            .with_context(|| format!("failed to open input file: {}", path.display()));
+    }
+    let mut bytes = Vec::new();
+    io::stdin()
+        .read_to_end(&mut bytes)
+        .context("failed to read stdin")?;
+    Ok(bytes)
+}
+
+// Output is created before writing, which would truncate the input if they are the same file
+fn check_distinct(input: &Path, output: &Path) -> Result<(), Error> {
+    if !is_stdio(output) && rewrite::is_same_file(input, output) {
+        bail!(
+            "refusing to overwrite input file: {} is the same file as {}",
+            output.display(),
+            input.display()
+        );
+    }
+    Ok(())
+}
+
+fn create_output(path: &Path, binary: bool, force: bool) -> Result<Box<dyn Write>, Error> {
+    if !is_stdio(path) {
+        let file = File::create(path)
+            .with_context(|| format!("failed to create output file: {}", path.display()))?;
+        return Ok(Box::new(file));
+    }
+    let stdout = io::stdout();
+    if binary && !force && stdout.is_terminal() {
+        bail!("refusing to write binary header to a terminal, use --force to write anyway");
+    }
+    Ok(Box::new(stdout))
+}
+
+// Read either binary header or definition text, which is told by the magic number
+fn parse_header(path: &Path, bytes: &[u8], format: DefinitionFormat) -> Result<Header, Error> {
+    if bytes.starts_with(b"NES\x1a") {
+        return Header::from_bytes(bytes);
+    }
+    let name = name_of(path);
+    let source = std::str::from_utf8(bytes)
+        .map_err(|_| anyhow!("{name} is neither NES file nor definition text"))?;
+    Header::from_definition(source.as_bytes(), format)
+        .map_err(|e| Diagnostic::locate(&name, source, &e).into())
+}
+
+// Locate the error in definition text, or just name the file if it's binary
+fn diagnose(path: &Path, bytes: &[u8], e: &Error) -> Diagnostic {
+    let name = name_of(path);
+    match std::str::from_utf8(bytes) {
+        Ok(source) if !bytes.starts_with(b"NES\x1a") => Diagnostic::locate(&name, source, e),
+        _ => Diagnostic::new(&name, e),
+    }
+}
+
+fn do_gen(
+    input: PathBuf,
+    output: PathBuf,
+    definition_format: DefinitionFormat,
+    encoding: Option<RomSizeEncoding>,
+    format: GenFormat,
+    force: bool,
+) -> Result<(), Error> {
+    let bytes = read_input(&input)?;
+    let mut header = parse_header(&input, &bytes, definition_format)?;
+
+    // Rendered before creating output not to leave a partial file on error
+    let mut rendered = Vec::new();
+    let mut render = || -> Result<(), Error> {
+        if let Some(encoding) = encoding {
+            header.encode_rom_sizes(encoding)?;
+        }
+        let header = header.clone();
+        match format {
+            GenFormat::Binary => header.into_bytes(&mut rendered)?,
+            GenFormat::Ca65 => header.into_asm(&mut rendered, AsmFormat::Ca65)?,
+            GenFormat::Asm6 => header.into_asm(&mut rendered, AsmFormat::Asm6)?,
+            GenFormat::Nesasm => header.into_asm(&mut rendered, AsmFormat::Nesasm)?,
+            GenFormat::NesasmInes => header.into_asm(&mut rendered, AsmFormat::NesasmInes)?,
+            GenFormat::C => header.into_code(&mut rendered, CodeFormat::C)?,
+            GenFormat::Rust => header.into_code(&mut rendered, CodeFormat::Rust)?,
+        }
+        Ok(())
+    };
+    render().map_err(|e| diagnose(&input, &bytes, &e))?;
+
+    check_distinct(&input, &output)?;
+    let mut output = create_output(&output, format == GenFormat::Binary, force)?;
+    output.write_all(&rendered)?;
+    output.flush()?;
+    Ok(())
+}
+
+fn do_dump(
+    input: PathBuf,
+    output: PathBuf,
+    input_format: DefinitionFormat,
+    definition_format: DefinitionFormat,
+    notation: MapperNotation,
+    boards: bool,
+) -> Result<(), Error> {
+    let bytes = read_input(&input)?;
+    let header = parse_header(&input, &bytes, input_format)?;
+    if boards {
+        // Boards go to stderr wherever the definition goes, not to mix with it on stdout
+        for board in header.matching_boards()? {
+            eprintln!("{}", board.name);
+        }
+    }
+    check_distinct(&input, &output)?;
+    let mut output = create_output(&output, false, false)?;
+    header.into_definition(&mut output, definition_format, notation)?;
+    output.flush()?;
+    Ok(())
+}
+
+fn do_info(input: PathBuf) -> Result<(), Error> {
+    let bytes = read_input(&input)?;
+    let header = parse_header(&input, &bytes, DefinitionFormat::Json)?;
+    let timing = header.timing.unwrap_or(Timing::RP2C02);
+    if let Value::Object(fields) = serde_json::to_value(&header)? {
+        for (key, value) in fields {
+            match value {
+                Value::Null => (),
+                Value::String(value) => println!("{key}: {value}"),
+                value => println!("{key}: {value}"),
+            }
+        }
+    }
+
+    let info = timing.info();
+    println!("region: {}", timing.region());
+    println!("cpu_clock: {:.0} Hz", info.cpu_clock);
+    println!("ppu_dots: {}", info.ppu_dots);
+    println!("scanlines: {}", info.scanlines);
+    println!("frame_rate: {:.4} Hz", info.frame_rate);
+    println!("cpu_ppu_ratio: {}", info.cpu_ppu_ratio);
+    Ok(())
+}
+
+fn do_layout(input: PathBuf) -> Result<(), Error> {
+    // Stdin has no metadata, so it's read until the end to know its length
+    let (file_length, header) = match is_stdio(&input) {
+        true => {
+            let bytes = read_input(&input)?;
+            (bytes.len() as u64, Header::from_bytes(bytes.as_slice())?)
+        }
+        false => {
+            let file = File::open(&input)
+                .with_context(|| format!("failed to open input file: {}", input.display()))?;
+            (file.metadata()?.len(), Header::from_bytes(file)?)
+        }
+    };
+    let layout = header.layout()?;
+
+    let mut sections = layout.sections();
+    sections.extend(layout.misc_rom_in(file_length).map(|r| ("misc_rom", r)));
+    for (name, range) in sections {
+        let size = range.end - range.start;
+        let line = format!(
+            "{name:<10}{:#010x}..{:#010x}  {size} bytes",
+            range.start, range.end
+        );
+        match range.end.checked_sub(file_length) {
+            Some(missing) if missing > 0 => {
+                println!("{line} (missing {})", missing.min(size))
+            }
+            _ => println!("{line}"),
         }
     }
+    println!("file      {file_length} bytes");
+    Ok(())
+}
+
+fn do_import(
+    input: PathBuf,
+    output: PathBuf,
+    definition_format: DefinitionFormat,
+) -> Result<(), Error> {
+    let bytes = read_input(&input)?;
+    let header = Header::from_asm(bytes.as_slice())?;
+    check_distinct(&input, &output)?;
+    let mut output = create_output(&output, false, false)?;
+    header.into_definition(&mut output, definition_format, MapperNotation::Number)?;
+    output.flush()?;
+    Ok(())
+}
+
+fn do_schema(output: PathBuf) -> Result<(), Error> {
+    let mut output = create_output(&output, false, false)?;
+    serde_json::to_writer_pretty(&mut output, &neshdr::header::schema::definition_schema())?;
+    output.flush()?;
     Ok(())
 }
 
-fn do_gen(input: PathBuf, output: PathBuf) -> Result<(), Error> {
-    let input = File::open(&input)
-        .with_context(|| format!("failed to open input file: {}", input.display()))?;
-    let output = File::create(&output)
-        .with_context(|| format!("failed to create output file: {}", output.display()))?;
-    Header::from_json(input)?.into_bytes(output)?;
+fn do_scan(paths: Vec<PathBuf>, recursive: bool, output: PathBuf) -> Result<(), Error> {
+    // Files are listed first, keeping errors of walking as records in place
+    let mut files = Vec::new();
+    for root in &paths {
+        // Sorted so that the output is the same between runs
+        let walk = WalkDir::new(root)
+            .max_depth(if recursive { usize::MAX } else { 1 })
+            .sort_by_file_name();
+        for entry in walk {
+            match entry {
+                Ok(entry) if entry.depth() == 0 && entry.file_type().is_file() => {
+                    files.push(Ok(entry.into_path()))
+                }
+                Ok(entry) if entry.file_type().is_file() && is_nes_file(entry.path()) => {
+                    files.push(Ok(entry.into_path()))
+                }
+                Ok(_) => (),
+                Err(e) => {
+                    let path = e.path().unwrap_or(root).to_path_buf();
+                    files.push(Err(Record::failed(&path, e)))
+                }
+            }
+        }
+    }
+
+    // A file which fails is recorded with its error so that the scan goes on
+    let records: Vec<Record> = files
+        .into_par_iter()
+        .map(|file| match file {
+            Ok(path) => Record::of(&path),
+            Err(record) => record,
+        })
+        .collect();
+    let mut output = create_output(&output, false, false)?;
+    for record in records {
+        serde_json::to_writer(&mut output, &record)?;
+        output.write_all(b"\n")?;
+    }
+    output.flush()?;
     Ok(())
 }
 
-fn do_dump(input: PathBuf, output: PathBuf) -> Result<(), Error> {
-    let input = File::open(&input)
-        .with_context(|| format!("failed to open input file: {}", input.display()))?;
-    let output = File::create(&output)
-        .with_context(|| format!("failed to create output file: {}", output.display()))?;
-    Header::from_bytes(input)?.into_json(output)?;
+fn do_set(
+    input: PathBuf,
+    fields: Vec<String>,
+    format: DefinitionFormat,
+    backup: bool,
+    journal: Option<&mut Journal>,
+) -> Result<(), Error> {
+    if is_stdio(&input) {
+        bail!("stdin can't be rewritten in place");
+    }
+    let fields = fields
+        .iter()
+        .map(|field| edit::parse_assignment(field))
+        .collect::<Result<Vec<_>, _>>()?;
+    let file = Rewrite::open(&input)?;
+    let name = name_of(&input);
+
+    // Only the bits of the changed fields are rewritten, so other bits are kept as is
+    if file.bytes().starts_with(b"NES\x1a") {
+        let original: [u8; 16] = file.bytes()[..16].try_into()?;
+        let header = edit::set_header_fields(&Header::from_bytes(file.bytes())?, &fields)?;
+        let mut bytes = file.bytes().to_vec();
+        bytes[..16].copy_from_slice(&header.patch(&original)?);
+        return file.commit(&bytes, backup, journal);
+    }
+
+    if journal.is_some() {
+        bail!("only edits of .nes files can be recorded in journal");
+    }
+    let source = std::str::from_utf8(file.bytes())
+        .map_err(|_| anyhow!("{name} is neither NES file nor definition text"))?;
+    let source = edit::set_fields(source, format, &fields);
+    Header::from_definition(source.as_bytes(), format)
+        .map_err(|e| Error::from(Diagnostic::locate(&name, &source, &e)))?;
+    file.commit(source.as_bytes(), backup, None)
+}
+
+fn is_nes_file(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|extension| extension.eq_ignore_ascii_case("nes"))
+}
+
+fn do_modernize(
+    input: PathBuf,
+    reverse: bool,
+    backup: bool,
+    journal: Option<&mut Journal>,
+) -> Result<(), Error> {
+    if is_stdio(&input) {
+        bail!("stdin can't be rewritten in place");
+    }
+    let file = Rewrite::open(&input)?;
+    let original: [u8; 16] = file
+        .bytes()
+        .get(..16)
+        .context("file is too short for header")?
+        .try_into()?;
+
+    // iNES is converted since it has no submapper to write
+    let mut converted = original;
+    let format = codec::format(&original)?;
+    match format {
+        Format::Nes2 => (),
+        Format::Ines => codec::ines_to_nes2(&mut converted),
+        Format::Archaic => bail!("archaic iNES header may have garbage in bytes 7 to 15"),
+    }
+    let mut header = Header::from_bytes(&converted[..])?;
+    let remapping = if reverse {
+        header.legacy()
+    } else {
+        header.modernize()
+    };
+    let Some(remapping) = remapping else {
+        return Ok(());
+    };
+
+    // Only the bits of the remapped fields are rewritten, so other bits are kept as is
+    let mut bytes = file.bytes().to_vec();
+    bytes[..16].copy_from_slice(&header.patch(&converted)?);
+    file.commit(&bytes, backup, journal)?;
+
+    let note = match format {
+        Format::Ines => ", converted from iNES",
+        _ => "",
+    };
+    println!(
+        "{}: mapper {}.{} -> {}.{} ({}{note})",
+        input.display(),
+        remapping.from.0,
+        remapping.from.1,
+        remapping.to.0,
+        remapping.to.1,
+        remapping.description
+    );
     Ok(())
 }
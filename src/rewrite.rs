@@ -0,0 +1,184 @@
+//! Safe in-place rewrite of files, which goes through a temporary file and rename.
+
+use crate::journal::Journal;
+use anyhow::{Context, Result, anyhow, bail};
+use sha2::{Digest, Sha256};
+use std::ffi::OsString;
+use std::fs::{self, OpenOptions, Permissions};
+use std::io::{ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use tempfile::NamedTempFile;
+
+/// File read to be rewritten in place
+#[derive(Debug, Clone)]
+pub struct Rewrite {
+    path: PathBuf,
+    bytes: Vec<u8>,
+    hash: [u8; 32],
+}
+
+impl Rewrite {
+    /// Read the whole file, remembering its content to detect changes before writing.
+    ///
+    /// Links are resolved so that the file they point to is rewritten rather than replaced.
+    pub fn open<P: AsRef<Path>>(path: P) -> Result<Self> {
+        let path = path.as_ref();
+        let bytes = fs::read(path)
+            .with_context(|| format!("failed to read input file: {}", path.display()))?;
+        let path = fs::canonicalize(path)
+            .with_context(|| format!("failed to resolve path: {}", path.display()))?;
+        let hash = hash(&bytes);
+        Ok(Rewrite { path, bytes, hash })
+    }
+
+    /// Returns the resolved path of the file
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Returns content of the file when it was read
+    pub fn bytes(&self) -> &[u8] {
+        &self.bytes
+    }
+
+    /// Returns SHA-256 of the file when it was read
+    pub fn hash(&self) -> [u8; 32] {
+        self.hash
+    }
+
+    /// Replace the file with the bytes, keeping its permissions and optionally backing up the
+    /// original into `<file>.bak`, or `<file>.bak.N` if it exists. Fails without writing anything
+    /// if the file has changed since read. The edit of the header is appended to the journal if
+    /// given.
+    pub fn commit(self, bytes: &[u8], backup: bool, journal: Option<&mut Journal>) -> Result<()> {
+        let path = &self.path;
+        let current = fs::read(path)
+            .with_context(|| format!("failed to read input file: {}", path.display()))?;
+        if hash(&current) != self.hash {
+            bail!("{} has changed since it was read", path.display());
+        }
+        let permissions = fs::metadata(path)?.permissions();
+
+        // Temporary file is in the same directory so that rename doesn't cross file systems
+        let directory = match path.parent() {
+            Some(parent) if !parent.as_os_str().is_empty() => parent,
+            _ => Path::new("."),
+        };
+        let mut temporary = NamedTempFile::new_in(directory).with_context(|| {
+            format!("failed to create temporary file in {}", directory.display())
+        })?;
+        temporary.write_all(bytes)?;
+        temporary.as_file().sync_all()?;
+        fs::set_permissions(temporary.path(), permissions.clone())?;
+
+        if backup {
+            write_backup(path, &current, permissions)?;
+        }
+        temporary
+            .persist(path)
+            .with_context(|| format!("failed to replace file: {}", path.display()))?;
+        if let Some(journal) = journal {
+            journal
+                .record(path, &self.bytes, bytes)
+                .map_err(|e| anyhow!("edited but failed to record in journal: {e}"))?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns path of the backup of the file, which is `<file>.bak` for index 0 and
+/// `<file>.bak.N` for the others
+pub fn backup_path(path: &Path, index: u32) -> PathBuf {
+    let mut name = OsString::from(path.as_os_str());
+    name.push(".bak");
+    if index != 0 {
+        name.push(format!(".{index}"));
+    }
+    PathBuf::from(name)
+}
+
+// Write the bytes into the first backup path which doesn't exist, never overwriting earlier ones
+fn write_backup(path: &Path, bytes: &[u8], permissions: Permissions) -> Result<()> {
+    for index in 0.. {
+        let backup = backup_path(path, index);
+        let context = || format!("failed to write backup file: {}", backup.display());
+        let mut file = match OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&backup)
+        {
+            Ok(file) => file,
+            Err(e) if e.kind() == ErrorKind::AlreadyExists => continue,
+            Err(e) => return Err(e).with_context(context),
+        };
+        file.write_all(bytes).with_context(context)?;
+        file.sync_all().with_context(context)?;
+        file.set_permissions(permissions).with_context(context)?;
+        return Ok(());
+    }
+    bail!("no free backup path for {}", path.display())
+}
+
+/// Returns SHA-256 of the bytes
+pub fn hash(bytes: &[u8]) -> [u8; 32] {
+    Sha256::digest(bytes).into()
+}
+
+/// Checks if both paths exist and resolve to the same file, following links
+pub fn is_same_file<P: AsRef<Path>, Q: AsRef<Path>>(a: P, b: Q) -> bool {
+    same_file::is_same_file(a, b).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::tempdir;
+
+    #[test]
+    fn backup_is_never_overwritten() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("game.nes");
+        fs::write(&path, b"first").unwrap();
+        Rewrite::open(&path)
+            .unwrap()
+            .commit(b"second", true, None)
+            .unwrap();
+        Rewrite::open(&path)
+            .unwrap()
+            .commit(b"third", true, None)
+            .unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"third");
+        assert_eq!(fs::read(backup_path(&path, 0)).unwrap(), b"first");
+        assert_eq!(fs::read(backup_path(&path, 1)).unwrap(), b"second");
+        assert!(!backup_path(&path, 2).exists());
+    }
+
+    #[test]
+    fn changed_file_is_not_replaced() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("game.nes");
+        fs::write(&path, b"first").unwrap();
+        let file = Rewrite::open(&path).unwrap();
+        fs::write(&path, b"changed").unwrap();
+        assert!(file.commit(b"second", true, None).is_err());
+        assert_eq!(fs::read(&path).unwrap(), b"changed");
+        assert!(!backup_path(&path, 0).exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn link_is_kept() {
+        let directory = tempdir().unwrap();
+        let target = directory.path().join("game.nes");
+        let link = directory.path().join("link.nes");
+        fs::write(&target, b"first").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        Rewrite::open(&link)
+            .unwrap()
+            .commit(b"second", true, None)
+            .unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().is_symlink());
+        assert_eq!(fs::read(&target).unwrap(), b"second");
+        assert!(backup_path(&fs::canonicalize(&target).unwrap(), 0).exists());
+    }
+}